@@ -1,21 +1,426 @@
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::WrapErr;
+use glob::Pattern;
+use il2cpp_metadata_raw::TypeDefinitionIndex;
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer};
+
+use super::{metadata::Metadata, profile::RuntimeProfile};
+
+const CPP_KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "and",
+    "and_eq",
+    "asm",
+    "auto",
+    "bitand",
+    "bitor",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "char8_t",
+    "char16_t",
+    "char32_t",
+    "class",
+    "compl",
+    "concept",
+    "const",
+    "consteval",
+    "constexpr",
+    "constinit",
+    "const_cast",
+    "continue",
+    "co_await",
+    "co_return",
+    "co_yield",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "dynamic_cast",
+    "else",
+    "enum",
+    "explicit",
+    "export",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "not_eq",
+    "nullptr",
+    "operator",
+    "or",
+    "or_eq",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "reinterpret_cast",
+    "requires",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "static_cast",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "thread_local",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typeid",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "wchar_t",
+    "while",
+    "xor",
+    "xor_eq",
+    // Not keywords, but a namespace or type named these breaks everything else
+    "std",
+    "bs_hook",
+    "il2cpp_utils",
+];
+
+// Macros defined by the il2cpp, beatsaber-hook and standard headers that get included
+const CPP_MACROS: &[&str] = &[
+    "NULL",
+    "EOF",
+    "errno",
+    "assert",
+    "offsetof",
+    "stdin",
+    "stdout",
+    "stderr",
+    "INFINITY",
+    "NAN",
+    "TRUE",
+    "FALSE",
+    "DOMAIN",
+    "OVERFLOW",
+    "UNDERFLOW",
+    "linux",
+    "unix",
+    "major",
+    "minor",
+    "CHAR_BIT",
+    "INT_MIN",
+    "INT_MAX",
+    "UINT_MAX",
+    "LONG_MIN",
+    "LONG_MAX",
+    "SIZE_MAX",
+    "classof",
+    "csnull",
+    "THROW_UNLESS",
+    "CRASH_UNLESS",
+    "RET_UNLESS",
+    "RET_V_UNLESS",
+    "RET_0_UNLESS",
+    "RET_NULLOPT_UNLESS",
+    "DEFINE_IL2CPP_ARG_TYPE",
+    "NEED_NO_BOX",
+];
 
 pub struct GenerationConfig {
     pub source_path: PathBuf,
     pub header_path: PathBuf,
+    /// Directory within `header_path` that types are written to
+    pub include_prefix: PathBuf,
+    /// Namespace used for types in the C# global namespace
+    pub global_namespace: String,
+    pub profile: RuntimeProfile,
+    pub wrapper_headers: WrapperHeaders,
+    pub filter: TypeFilter,
+    pub indent: IndentStyle,
+}
+
+/// How generated code is indented
+/// `indent = "tabs"` or `indent = { spaces = 4 }` in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndentStyle {
+    Tabs,
+    Spaces(u8),
+}
+
+impl Default for IndentStyle {
+    fn default() -> Self {
+        IndentStyle::Spaces(4)
+    }
+}
+
+impl IndentStyle {
+    /// The whitespace before a line `level` levels deep
+    pub fn prefix(&self, level: u16) -> String {
+        match self {
+            IndentStyle::Tabs => "\t".repeat(level.into()),
+            IndentStyle::Spaces(width) => " ".repeat(usize::from(level) * usize::from(*width)),
+        }
+    }
+}
+
+/// Headers of the runtime support library that generated types depend on
+/// Defaults to the headers of the runtime profile
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WrapperHeaders {
+    pub wrapper_type: PathBuf,
+    pub string: PathBuf,
+    pub array: PathBuf,
+    /// Method invocation and class lookup
+    pub runtime: PathBuf,
+}
+
+impl Default for WrapperHeaders {
+    fn default() -> Self {
+        Self {
+            wrapper_type: "beatsaber-hook/shared/utils/base-wrapper-type.hpp".into(),
+            string: "beatsaber-hook/shared/utils/typedefs-string.hpp".into(),
+            array: "beatsaber-hook/shared/utils/typedefs-array.hpp".into(),
+            runtime: "beatsaber-hook/shared/utils/il2cpp-utils.hpp".into(),
+        }
+    }
+}
+
+/// The config file passed to the CLI, as TOML or JSON
+/// Everything is optional, options given on the command line take priority
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Used for `header_path` and `source_path` when they are not given
+    pub output: Option<PathBuf>,
+    pub header_path: Option<PathBuf>,
+    pub source_path: Option<PathBuf>,
+    pub include_prefix: Option<PathBuf>,
+    pub global_namespace: Option<String>,
+    pub profile: Option<RuntimeProfile>,
+    pub wrapper_headers: Option<WrapperHeaders>,
+    pub filter: TypeFilter,
+    pub indent: Option<IndentStyle>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read config file {path:?}"))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&content)
+                .wrap_err_with(|| format!("Invalid JSON config file {path:?}")),
+            _ => toml::from_str(&content)
+                .wrap_err_with(|| format!("Invalid TOML config file {path:?}")),
+        }
+    }
+
+    /// The settings to generate with, those given on the command line replace the file's
+    pub fn into_config(
+        self,
+        output: Option<PathBuf>,
+        profile: Option<RuntimeProfile>,
+        filter: TypeFilter,
+    ) -> GenerationConfig {
+        let (header_path, source_path) = match output {
+            Some(output) => (output.join("include"), output.join("src")),
+            None => {
+                let output = self.output.unwrap_or_else(|| PathBuf::from("./codegen"));
+                (
+                    self.header_path.unwrap_or_else(|| output.join("include")),
+                    self.source_path.unwrap_or_else(|| output.join("src")),
+                )
+            }
+        };
+
+        let profile = profile.or(self.profile).unwrap_or_default();
+
+        GenerationConfig {
+            header_path,
+            source_path,
+            include_prefix: self.include_prefix.unwrap_or_default(),
+            global_namespace: self
+                .global_namespace
+                .unwrap_or_else(|| "GlobalNamespace".to_string()),
+            profile,
+            wrapper_headers: self
+                .wrapper_headers
+                .unwrap_or_else(|| profile.default_headers()),
+            filter: filter.or(self.filter),
+            indent: self.indent.unwrap_or_default(),
+        }
+    }
+}
+
+fn deserialize_patterns<'de, D>(deserializer: D) -> Result<Vec<Pattern>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|g| Pattern::new(g).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Restricts which root types are generated
+/// Types they depend on are still generated
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TypeFilter {
+    pub include_images: Vec<String>,
+    pub exclude_images: Vec<String>,
+    pub include_namespaces: Vec<String>,
+    pub exclude_namespaces: Vec<String>,
+    // Globs on the full name, e.g. UnityEngine.*
+    #[serde(deserialize_with = "deserialize_patterns")]
+    pub include_names: Vec<Pattern>,
+    #[serde(deserialize_with = "deserialize_patterns")]
+    pub exclude_names: Vec<Pattern>,
+}
+
+impl TypeFilter {
+    /// Each list that is empty here is taken from `fallback`
+    /// so lists given on the command line replace those of the config file
+    pub fn or(self, fallback: TypeFilter) -> TypeFilter {
+        fn or<T>(list: Vec<T>, fallback: Vec<T>) -> Vec<T> {
+            match list.is_empty() {
+                true => fallback,
+                false => list,
+            }
+        }
+
+        TypeFilter {
+            include_images: or(self.include_images, fallback.include_images),
+            exclude_images: or(self.exclude_images, fallback.exclude_images),
+            include_namespaces: or(self.include_namespaces, fallback.include_namespaces),
+            exclude_namespaces: or(self.exclude_namespaces, fallback.exclude_namespaces),
+            include_names: or(self.include_names, fallback.include_names),
+            exclude_names: or(self.exclude_names, fallback.exclude_names),
+        }
+    }
+
+    fn has_includes(&self) -> bool {
+        !self.include_images.is_empty()
+            || !self.include_namespaces.is_empty()
+            || !self.include_names.is_empty()
+    }
+
+    fn namespace_matches(prefix: &str, namespace: &str) -> bool {
+        namespace == prefix
+            || namespace
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('.'))
+    }
+
+    /// Without any include filters every type is included
+    /// Otherwise, a type has to match at least one of them
+    pub fn matches(&self, metadata: &Metadata, tdi: TypeDefinitionIndex) -> bool {
+        let t = &metadata.metadata.type_definitions[tdi as usize];
+        let image = metadata.type_image_name(tdi);
+        let namespace = metadata.metadata.get_str(t.namespace_index).unwrap();
+        let full_name = metadata.type_full_name(tdi);
+
+        let included = !self.has_includes()
+            || self.include_images.iter().any(|i| i == image)
+            || self
+                .include_namespaces
+                .iter()
+                .any(|n| Self::namespace_matches(n, namespace))
+            || self.include_names.iter().any(|p| p.matches(&full_name));
+
+        let excluded = self.exclude_images.iter().any(|i| i == image)
+            || self
+                .exclude_namespaces
+                .iter()
+                .any(|n| Self::namespace_matches(n, namespace))
+            || self.exclude_names.iter().any(|p| p.matches(&full_name));
+
+        included && !excluded
+    }
+}
+
+/// Escapes a character like a universal character name, without the backslash
+fn escape_char(c: char) -> String {
+    match c as u32 {
+        code @ 0..=0xFFFF => format!("u{code:04X}"),
+        code => format!("U{code:08X}"),
+    }
+}
+
+/// Only ASCII punctuation becomes a single underscore, everything else keeps its own spelling
+/// so different C# names stay different
+fn cpp_identifier(string: &str) -> String {
+    let mut name = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => name.push(c),
+            // Double underscores are reserved, so a second one is escaped
+            '_' if name.ends_with('_') => name.push_str(&escape_char(c)),
+            '_' => name.push(c),
+            c if c.is_ascii() && name.ends_with('_') => {}
+            c if c.is_ascii() => name.push('_'),
+            c => name.push_str(&escape_char(c)),
+        }
+    }
+
+    // _Foo is reserved too, so the underscore is escaped
+    if let Some(rest) = name.strip_prefix('_')
+        && rest.starts_with(|c: char| c.is_ascii_uppercase())
+    {
+        name = format!("{}{rest}", escape_char('_'));
+    }
+
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+
+    if CPP_KEYWORDS.contains(&name.as_str())
+        || CPP_MACROS.contains(&name.as_str())
+        || name.starts_with("IL2CPP_")
+    {
+        name.push('_');
+    }
+
+    name
 }
 
 impl GenerationConfig {
     pub fn namespace_cpp(&self, string: &str) -> String {
         if string.is_empty() {
-            "GlobalNamespace".to_owned()
+            self.global_namespace.clone()
         } else {
-            string.replace(['<', '>', '`', '/'], "_").replace('.', "::")
+            string.split('.').map(|n| self.name_cpp(n)).join("::")
         }
     }
+    /// Turns any C# name into a valid C++ identifier
     pub fn name_cpp(&self, string: &str) -> String {
-        // Coincidentally the same as path_name
-        string.replace(['<', '`', '>', '/', '.'], "_")
+        cpp_identifier(string)
     }
     pub fn namespace_path(&self, string: &str) -> String {
         string.replace(['<', '>', '`', '/'], "_").replace('.', "/")
@@ -24,3 +429,239 @@ impl GenerationConfig {
         string.replace(['<', '>', '`', '.', '/'], "_")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_valid_identifier(name: &str) -> bool {
+        name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.contains("__")
+            && !(name.starts_with('_') && name[1..].starts_with(|c: char| c.is_ascii_uppercase()))
+    }
+
+    #[test]
+    fn keeps_plain_names() {
+        assert_eq!(cpp_identifier("Foo"), "Foo");
+        assert_eq!(cpp_identifier("foo_bar1"), "foo_bar1");
+        assert_eq!(cpp_identifier("_foo"), "_foo");
+    }
+
+    #[test]
+    fn collapses_ascii_punctuation() {
+        assert_eq!(cpp_identifier("List`1"), "List_1");
+        assert_eq!(cpp_identifier("<>c"), "_c");
+        assert_eq!(cpp_identifier("a.<b>"), "a_b_");
+    }
+
+    #[test]
+    fn encodes_non_ascii() {
+        assert_eq!(cpp_identifier("a_ü"), "a_u00FC");
+        assert_ne!(cpp_identifier("a_ü"), cpp_identifier("a_ö"));
+        assert_eq!(cpp_identifier("a<ü"), "a_u00FC");
+        assert_eq!(cpp_identifier("😀"), "U0001F600");
+    }
+
+    #[test]
+    fn keeps_underscores_apart() {
+        assert_ne!(cpp_identifier("a__b"), cpp_identifier("a_b"));
+        assert_ne!(cpp_identifier("a___b"), cpp_identifier("a__b"));
+        assert_ne!(cpp_identifier("_Foo"), cpp_identifier("Foo_"));
+        assert_ne!(cpp_identifier("_Foo"), cpp_identifier("Foo"));
+    }
+
+    #[test]
+    fn makes_valid_identifiers() {
+        for name in [
+            "",
+            "1st",
+            "a__b",
+            "__a",
+            "_Foo",
+            "__Foo",
+            "<Foo>k__BackingField",
+            "a-ü",
+            "IL2CPP_X",
+        ] {
+            let cpp_name = cpp_identifier(name);
+            assert!(is_valid_identifier(&cpp_name), "{name} became {cpp_name}");
+        }
+    }
+
+    #[test]
+    fn avoids_keywords_and_macros() {
+        for name in CPP_KEYWORDS.iter().chain(CPP_MACROS) {
+            let cpp_name = cpp_identifier(name);
+            assert_ne!(&cpp_name, name);
+            assert!(!CPP_KEYWORDS.contains(&cpp_name.as_str()));
+            assert!(!CPP_MACROS.contains(&cpp_name.as_str()));
+        }
+        assert_eq!(cpp_identifier("IL2CPP_EXPORT"), "IL2CPP_EXPORT_");
+    }
+
+    #[test]
+    fn keyword_list_is_valid() {
+        assert!(CPP_KEYWORDS.iter().all_unique());
+        assert!(CPP_KEYWORDS.iter().all(|k| k
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')));
+        for keyword in [
+            "class",
+            "delete",
+            "namespace",
+            "template",
+            "co_await",
+            "requires",
+        ] {
+            assert!(CPP_KEYWORDS.contains(&keyword), "{keyword} is missing");
+        }
+    }
+
+    fn load(file_name: &str, content: &str) -> color_eyre::Result<ConfigFile> {
+        let path = std::env::temp_dir().join(format!("cordl-{}-{file_name}", std::process::id()));
+        fs::write(&path, content).unwrap();
+        let config_file = ConfigFile::load(&path);
+        fs::remove_file(&path).unwrap();
+        config_file
+    }
+
+    fn names(patterns: &[Pattern]) -> Vec<&str> {
+        patterns.iter().map(|p| p.as_str()).collect()
+    }
+
+    fn filter(include_namespaces: &[&str], exclude_names: &[&str]) -> TypeFilter {
+        TypeFilter {
+            include_namespaces: include_namespaces.iter().map(|n| n.to_string()).collect(),
+            exclude_names: exclude_names
+                .iter()
+                .map(|n| Pattern::new(n).unwrap())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    const TOML_CONFIG: &str = r#"
+output = "out"
+header_path = "headers"
+global_namespace = "Global"
+profile = "il2cpp-api"
+indent = "tabs"
+
+[filter]
+include_namespaces = ["UnityEngine"]
+exclude_names = ["*Internal*"]
+"#;
+
+    #[test]
+    fn loads_toml_and_json_alike() {
+        let toml = load("config.toml", TOML_CONFIG).unwrap();
+        let json = load(
+            "config.json",
+            r#"{
+                "output": "out",
+                "header_path": "headers",
+                "global_namespace": "Global",
+                "profile": "il2cpp-api",
+                "indent": "tabs",
+                "filter": { "include_namespaces": ["UnityEngine"], "exclude_names": ["*Internal*"] }
+            }"#,
+        )
+        .unwrap();
+
+        for config_file in [toml, json] {
+            assert_eq!(config_file.output, Some(PathBuf::from("out")));
+            assert_eq!(config_file.header_path, Some(PathBuf::from("headers")));
+            assert_eq!(config_file.global_namespace.as_deref(), Some("Global"));
+            assert_eq!(config_file.profile, Some(RuntimeProfile::Il2cppApi));
+            assert_eq!(config_file.indent, Some(IndentStyle::Tabs));
+            assert_eq!(config_file.filter.include_namespaces, ["UnityEngine"]);
+            assert_eq!(names(&config_file.filter.exclude_names), ["*Internal*"]);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_config_keys() {
+        assert!(load("unknown.toml", "outptu = \"out\"\n").is_err());
+    }
+
+    #[test]
+    fn uses_the_config_file_without_command_line_options() {
+        let config = load("config.toml", TOML_CONFIG).unwrap().into_config(
+            None,
+            None,
+            TypeFilter::default(),
+        );
+
+        assert_eq!(config.header_path, PathBuf::from("headers"));
+        // Only the header path is set, the source path still follows the output
+        assert_eq!(config.source_path, PathBuf::from("out/src"));
+        assert_eq!(config.global_namespace, "Global");
+        assert_eq!(config.profile, RuntimeProfile::Il2cppApi);
+        assert_eq!(config.indent, IndentStyle::Tabs);
+        assert_eq!(config.filter.include_namespaces, ["UnityEngine"]);
+        assert_eq!(names(&config.filter.exclude_names), ["*Internal*"]);
+    }
+
+    #[test]
+    fn prefers_command_line_options() {
+        let config = load("config.toml", TOML_CONFIG).unwrap().into_config(
+            Some(PathBuf::from("cli")),
+            Some(RuntimeProfile::BsHook),
+            filter(&["System"], &[]),
+        );
+
+        // The output given on the command line replaces every path of the file
+        assert_eq!(config.header_path, PathBuf::from("cli/include"));
+        assert_eq!(config.source_path, PathBuf::from("cli/src"));
+        assert_eq!(config.profile, RuntimeProfile::BsHook);
+        assert_eq!(config.filter.include_namespaces, ["System"]);
+        // Lists not given on the command line still come from the file
+        assert_eq!(names(&config.filter.exclude_names), ["*Internal*"]);
+    }
+
+    #[test]
+    fn defaults_without_either() {
+        let config = ConfigFile::default().into_config(None, None, TypeFilter::default());
+
+        assert_eq!(config.header_path, PathBuf::from("./codegen/include"));
+        assert_eq!(config.source_path, PathBuf::from("./codegen/src"));
+        assert_eq!(config.global_namespace, "GlobalNamespace");
+        assert_eq!(config.profile, RuntimeProfile::BsHook);
+        assert_eq!(config.indent, IndentStyle::Spaces(4));
+        assert!(!config.filter.has_includes());
+    }
+
+    #[test]
+    fn merges_filters_list_by_list() {
+        let merged = filter(&["System"], &[]).or(filter(&["UnityEngine"], &["*Internal*"]));
+        assert_eq!(merged.include_namespaces, ["System"]);
+        assert_eq!(names(&merged.exclude_names), ["*Internal*"]);
+
+        // Lists replace each other rather than being joined
+        let merged = filter(&["System", "Mono"], &["A*"]).or(filter(&["UnityEngine"], &["B*"]));
+        assert_eq!(merged.include_namespaces, ["System", "Mono"]);
+        assert_eq!(names(&merged.exclude_names), ["A*"]);
+
+        let merged = TypeFilter::default().or(TypeFilter::default());
+        assert!(!merged.has_includes());
+        assert!(merged.exclude_names.is_empty());
+    }
+
+    #[test]
+    fn matches_namespaces_and_their_children() {
+        assert!(TypeFilter::namespace_matches("UnityEngine", "UnityEngine"));
+        assert!(TypeFilter::namespace_matches(
+            "UnityEngine",
+            "UnityEngine.UI"
+        ));
+        assert!(!TypeFilter::namespace_matches(
+            "UnityEngine",
+            "UnityEngineX"
+        ));
+        assert!(!TypeFilter::namespace_matches(
+            "UnityEngine.UI",
+            "UnityEngine"
+        ));
+    }
+}
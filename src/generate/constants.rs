@@ -17,6 +17,10 @@ pub const METHOD_ATTRIBUTE_HIDE_BY_SIG: u16 = 0x0080;
 pub const METHOD_ATTRIBUTE_ABSTRACT: u16 = 0x0400;
 pub const METHOD_ATTRIBUTE_SPECIAL_NAME: u16 = 0x0800;
 
+pub const GENERIC_PARAMETER_ATTRIBUTE_REFERENCE_TYPE_CONSTRAINT: u16 = 0x0004;
+pub const GENERIC_PARAMETER_ATTRIBUTE_NOT_NULLABLE_VALUE_TYPE_CONSTRAINT: u16 = 0x0008;
+pub const GENERIC_PARAMETER_ATTRIBUTE_DEFAULT_CONSTRUCTOR_CONSTRAINT: u16 = 0x0010;
+
 pub trait MethodDefintionExtensions {
     fn is_public_method(&self) -> bool;
     fn is_abstract_method(&self) -> bool;
@@ -85,6 +89,7 @@ impl TypeExtentions for Type {
 pub trait TypeDefinitionExtensions {
     fn is_value_type(&self) -> bool;
     fn is_enum_type(&self) -> bool;
+    fn is_interface(&self) -> bool;
 }
 
 impl TypeDefinitionExtensions for Il2CppTypeDefinition {
@@ -95,4 +100,8 @@ impl TypeDefinitionExtensions for Il2CppTypeDefinition {
     fn is_enum_type(&self) -> bool {
         self.bitfield & 2 != 0
     }
+
+    fn is_interface(&self) -> bool {
+        self.flags & TYPE_ATTRIBUTE_INTERFACE != 0
+    }
 }
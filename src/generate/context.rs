@@ -1,6 +1,7 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::{create_dir_all, remove_file, File},
+    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -14,9 +15,10 @@ use crate::generate::members::CppInclude;
 
 use super::{
     config::GenerationConfig,
-    cpp_type::{self, CppType},
+    cpp_type::{self, CppType, DependencyKind},
     cs_type::CSType,
-    metadata::Metadata,
+    members::CppForwardDeclare,
+    metadata::{Metadata, PointerSize},
     writer::{CppWriter, Writable},
 };
 
@@ -30,11 +32,18 @@ pub struct CppContext {
     // combined header
     pub fundamental_path: PathBuf,
 
+    // Includes are written relative to it, so the generated headers can be moved
+    include_root: PathBuf,
+
     // Types to write, typedef
-    typedef_types: HashMap<TypeTag, CppType>,
+    // Sorted, so they are written in the same order every run
+    typedef_types: BTreeMap<TypeTag, CppType>,
+
+    // Layouts are only valid for the target they were read from
+    pointer_size: PointerSize,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum TypeTag {
     TypeDefinition(u32),
     Type(usize),
@@ -88,10 +97,25 @@ impl CppContext {
         &self.typedef_path
     }
 
-    pub fn get_types(&self) -> &HashMap<TypeTag, CppType> {
+    /// How one of this context's headers is included, relative to the include root
+    pub fn relative_include_path(&self, header: &Path) -> PathBuf {
+        header
+            .strip_prefix(&self.include_root)
+            .unwrap_or(header)
+            .to_path_buf()
+    }
+
+    pub fn get_types(&self) -> &BTreeMap<TypeTag, CppType> {
         &self.typedef_types
     }
 
+    /// The types of this context and all of their nested types
+    pub fn get_types_flattened(&self) -> impl Iterator<Item = &CppType> {
+        self.typedef_types
+            .values()
+            .flat_map(|t| std::iter::once(t).chain(t.nested_types_flattened().into_values()))
+    }
+
     // TODO: Move out, this is CSContext
     fn make(
         metadata: &Metadata,
@@ -109,27 +133,26 @@ impl CppContext {
 
         let ns_path = config.namespace_path(ns);
         let path = if ns_path.is_empty() {
-            "GlobalNamespace/".to_string()
+            format!("{}/", config.global_namespace)
         } else {
             ns_path + "/"
         };
+        let header_path = config.header_path.join(&config.include_prefix);
         let mut x = CppContext {
-            typedef_path: config.header_path.join(format!(
+            typedef_path: header_path.join(format!(
                 "{}__{}_def.hpp",
                 path,
                 &config.path_name(name)
             )),
-            type_impl_path: config.header_path.join(format!(
+            type_impl_path: header_path.join(format!(
                 "{}__{}_impl.hpp",
                 path,
                 &config.path_name(name)
             )),
-            fundamental_path: config.header_path.join(format!(
-                "{}{}.hpp",
-                path,
-                &config.path_name(name)
-            )),
+            fundamental_path: header_path.join(format!("{}{}.hpp", path, &config.path_name(name))),
             typedef_types: Default::default(),
+            include_root: config.header_path.clone(),
+            pointer_size: metadata.pointer_size,
         };
         match CppType::make_cpp_type(metadata, config, tag) {
             Some(cpptype) => {
@@ -147,7 +170,7 @@ impl CppContext {
         x
     }
 
-    pub fn write(&self) -> color_eyre::Result<()> {
+    pub fn write(&self, config: &GenerationConfig) -> color_eyre::Result<()> {
         // Write typedef file first
         if Path::exists(self.typedef_path.as_path()) {
             remove_file(self.typedef_path.as_path())?;
@@ -166,50 +189,78 @@ impl CppContext {
         }
 
         println!("Writing {:?}", self.typedef_path.as_path());
-        let mut typedef_writer = CppWriter {
-            stream: File::create(self.typedef_path.as_path())?,
-            indent: 0,
-            newline: true,
-        };
-        let mut typeimpl_writer = CppWriter {
-            stream: File::create(self.type_impl_path.as_path())?,
-            indent: 0,
-            newline: true,
-        };
-        let mut fundamental_writer = CppWriter {
-            stream: File::create(self.fundamental_path.as_path())?,
-            indent: 0,
-            newline: true,
-        };
+        let mut typedef_writer = CppWriter::new(
+            File::create(self.typedef_path.as_path())?,
+            config.profile,
+            config.indent,
+        );
+        let mut typeimpl_writer = CppWriter::new(
+            File::create(self.type_impl_path.as_path())?,
+            config.profile,
+            config.indent,
+        );
+        let mut fundamental_writer = CppWriter::new(
+            File::create(self.fundamental_path.as_path())?,
+            config.profile,
+            config.indent,
+        );
+
+        // Every header is included from several others
+        for writer in [
+            &mut typedef_writer,
+            &mut typeimpl_writer,
+            &mut fundamental_writer,
+        ] {
+            writeln!(writer, "#pragma once")?;
+            writer.blank_line()?;
+        }
 
         // Write includes for typedef
-        self.typedef_types
-            .values()
+        self.get_types_flattened()
             .flat_map(|t| &t.requirements.required_includes)
-            .unique()
+            .sorted()
+            .dedup()
             .try_for_each(|i| i.write(&mut typedef_writer))?;
+        typedef_writer.blank_line()?;
 
         // write forward declares
         {
-            self.typedef_types
-                .values()
+            // Types of this context are defined here already
+            let own_include = CppInclude::new_context(self);
+            let forward_declares = self
+                .get_types_flattened()
                 .flat_map(|t| &t.requirements.forward_declares)
+                .filter(|(_, i)| *i != own_include)
                 .map(|(d, _)| d)
-                .unique()
-                // TODO: Check forward declare is not of own type
-                .try_for_each(|i| i.write(&mut typedef_writer))?;
-
-            CppInclude::new(self.type_impl_path.to_path_buf()).write(&mut typeimpl_writer)?;
-            // This is likely not necessary
-            // self.typedef_types
-            //     .values()
-            //     .flat_map(|t| &t.requirements.forward_declares)
-            //     .map(|(_, i)| i)
-            //     .unique()
-            //     // TODO: Check forward declare is not of own type
-            //     .try_for_each(|i| i.write(&mut typeimpl_writer))?;
+                .sorted()
+                .dedup();
+            CppForwardDeclare::write_grouped(forward_declares, &mut typedef_writer)?;
+
+            // Method bodies use what the definitions only forward declared
+            CppInclude::new_context_header(self, &self.typedef_path).write(&mut typeimpl_writer)?;
+            self.get_types_flattened()
+                .flat_map(|t| {
+                    t.requirements
+                        .forward_declares
+                        .iter()
+                        .map(|(_, i)| i)
+                        .chain(&t.requirements.impl_includes)
+                })
+                .filter(|i| **i != own_include)
+                .sorted()
+                .dedup()
+                .try_for_each(|i| i.write(&mut typeimpl_writer))?;
+            typeimpl_writer.blank_line()?;
         }
 
+        typedef_writer.blank_line()?;
+        writeln!(
+            typedef_writer,
+            "static_assert(sizeof(void*) == {}, \"Generated for a {} bit target\");",
+            self.pointer_size.bytes(),
+            self.pointer_size.bytes() * 8
+        )?;
+
         for t in self.typedef_types.values() {
             if t.nested {
                 continue;
@@ -218,16 +269,16 @@ impl CppContext {
             t.write_impl(&mut typeimpl_writer)?;
         }
 
-        CppInclude::new(self.typedef_path.to_path_buf()).write(&mut fundamental_writer)?;
-        CppInclude::new(self.type_impl_path.to_path_buf()).write(&mut fundamental_writer)?;
+        CppInclude::new_context_header(self, &self.typedef_path).write(&mut fundamental_writer)?;
+        CppInclude::new_context_header(self, &self.type_impl_path)
+            .write(&mut fundamental_writer)?;
 
-        // TODO: Write type impl and fundamental files here
         Ok(())
     }
 }
 
 pub struct CppContextCollection {
-    all_contexts: HashMap<TypeTag, CppContext>,
+    all_contexts: BTreeMap<TypeTag, CppContext>,
     alias_context: HashMap<TypeTag, TypeTag>,
     filled_types: HashSet<TypeTag>,
     filling_types: HashSet<TypeTag>,
@@ -243,23 +294,26 @@ impl CppContextCollection {
             "Do not fill a child"
         );
 
-        let context_tag = self.get_context_root_tag(type_tag);
+        let context_tag = self.get_context_root_tag(metadata, type_tag);
 
-        if self.filled_types.contains(&type_tag) {
+        if self.filled_types.contains(&type_tag) || self.filling_types.contains(&type_tag) {
             return;
         }
 
         self.make_from(metadata, config, type_tag);
 
+        // Filled on a copy, so the type and its nested types can still be looked up meanwhile
         let cpp_type_entry = self
             .all_contexts
-            .get_mut(&context_tag)
+            .get(&context_tag)
             .expect("No cpp context")
             .typedef_types
-            .remove_entry(&type_tag);
+            .get(&type_tag)
+            .cloned();
         self.filling_types.insert(type_tag);
 
-        if let Some((t, mut cpp_type)) = cpp_type_entry {
+        let filled = cpp_type_entry.is_some();
+        if let Some(mut cpp_type) = cpp_type_entry {
             cpp_type.fill_from_il2cpp(metadata, config, self, tdi);
 
             // Now do children
@@ -272,11 +326,109 @@ impl CppContextCollection {
                 .get_mut(&context_tag)
                 .expect("No cpp context")
                 .typedef_types
-                .insert(t, cpp_type);
+                .insert(type_tag, cpp_type);
         }
 
         self.filled_types.insert(type_tag);
         self.filling_types.remove(&type_tag);
+
+        // Nested types can only be filled once the owner is back in its context
+        if filled {
+            self.fill_nested_types(metadata, config, type_tag);
+        }
+    }
+
+    /// Fills the contexts that were only made while filling other types
+    /// e.g. the types found through `cppify_name_il2cpp`, until no new ones show up
+    pub fn fill_dependencies(&mut self, metadata: &Metadata, config: &GenerationConfig) {
+        loop {
+            let unfilled = self
+                .all_contexts
+                .keys()
+                .filter(|tag| !self.filled_types.contains(tag))
+                .copied()
+                .collect_vec();
+
+            if unfilled.is_empty() {
+                break;
+            }
+
+            for tag in unfilled {
+                let root_tag = self.get_context_root_tag(metadata, tag);
+
+                // Nested types are filled through their root type
+                if root_tag != tag {
+                    self.filled_types.insert(tag);
+                }
+                self.fill(metadata, config, root_tag);
+            }
+        }
+    }
+
+    /// Finds contexts whose definitions need each other, which no include order can satisfy
+    /// Only complete dependencies include definitions, a forward declare does for the rest,
+    /// so each of these cycles is reported as an error and its contexts can't be written
+    /// Neither can any context that includes their headers, directly or through others
+    pub fn find_include_cycles(&self, metadata: &Metadata) -> HashSet<TypeTag> {
+        let includes = self.context_graph(metadata, |kind| kind == DependencyKind::Complete);
+
+        let mut skipped: BTreeSet<TypeTag> = BTreeSet::new();
+        // Dependencies on the context itself are left out, so one context is never a cycle
+        for cycle in strongly_connected_components(&includes)
+            .into_iter()
+            .filter(|c| c.len() > 1)
+        {
+            println!(
+                "Error: include cycle between definitions, not writing: {}",
+                cycle
+                    .iter()
+                    .map(|t| self.describe_context(metadata, *t))
+                    .join(", ")
+            );
+            skipped.extend(cycle);
+        }
+
+        // Forward declared types are included by the method bodies
+        let dependencies = self.context_graph(metadata, |_| true);
+        let dependents = dependents(&dependencies, &skipped);
+        for tag in &dependents {
+            println!(
+                "Error: {} needs the headers of an include cycle, not writing it",
+                self.describe_context(metadata, *tag)
+            );
+        }
+
+        skipped.into_iter().chain(dependents).collect()
+    }
+
+    /// Each context to the other contexts it has dependencies of a `kind` on
+    fn context_graph(
+        &self,
+        metadata: &Metadata,
+        kind: impl Fn(DependencyKind) -> bool,
+    ) -> BTreeMap<TypeTag, Vec<TypeTag>> {
+        self.all_contexts
+            .iter()
+            .map(|(tag, context)| {
+                let dependencies = context
+                    .get_types_flattened()
+                    .flat_map(|t| &t.requirements.dependencies)
+                    .filter(|(_, k)| kind(**k))
+                    .map(|(dependency, _)| self.get_context_root_tag(metadata, *dependency))
+                    .filter(|dependency| dependency != tag)
+                    .unique()
+                    .sorted()
+                    .collect_vec();
+                (*tag, dependencies)
+            })
+            .collect()
+    }
+
+    fn describe_context(&self, metadata: &Metadata, tag: TypeTag) -> String {
+        match tag {
+            TypeTag::TypeDefinition(tdi) => metadata.type_full_name(tdi),
+            _ => format!("{tag:?}"),
+        }
     }
 
     fn alias_nested_types(&mut self, owner: &CppType, root_tag: TypeTag) {
@@ -285,8 +437,7 @@ impl CppContextCollection {
             //     "Aliasing {:?} to {:?}",
             //     nested_type.self_tag, owner.self_tag
             // );
-            self.alias_context
-                .insert(nested_type.self_tag, root_tag);
+            self.alias_context.insert(nested_type.self_tag, root_tag);
             self.alias_nested_types(nested_type, root_tag);
         }
     }
@@ -302,43 +453,54 @@ impl CppContextCollection {
             .get_cpp_type(metadata, config, owner_type_tag)
             .unwrap_or_else(|| panic!("Owner does not exist {owner_type_tag:?}"));
 
-        let nested_tags = owner.nested_types.iter().map(|n| n.self_tag).collect_vec();
-        // own it now
-        // we clone, then write later
-        // potentially bad
+        // Like the owner, they are filled on a copy while the context keeps the unfilled ones
         let mut nested_types = owner.nested_types.clone();
+        self.fill_nested_types_recursive(metadata, config, &mut nested_types);
 
-        nested_tags.into_iter().for_each(|nested_tag| {
-            self.filling_types.insert(nested_tag);
+        self.get_cpp_type(metadata, config, owner_type_tag)
+            .unwrap()
+            .nested_types = nested_types;
+    }
 
-            {
-                let nested_type = nested_types
-                    .iter_mut()
-                    .find(|n| n.self_tag == nested_tag)
-                    .unwrap();
-                let tdi = CppType::get_tag_tdi(nested_tag);
+    fn fill_nested_types_recursive(
+        &mut self,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        nested_types: &mut [CppType],
+    ) {
+        for nested_type in nested_types {
+            let nested_tag = nested_type.self_tag;
+            self.filling_types.insert(nested_tag);
 
-                nested_type.fill_from_il2cpp(metadata, config, self, tdi);
+            let tdi = CppType::get_tag_tdi(nested_tag);
+            nested_type.fill_from_il2cpp(metadata, config, self, tdi);
 
-                // DO not recurse
-                // self.fill_nested_types(metadata, config, nested_tag);
-            }
             self.filled_types.insert(nested_tag);
             self.filling_types.remove(&nested_tag);
-        });
 
-        self.get_cpp_type(metadata, config, owner_type_tag)
-            .unwrap()
-            .nested_types = nested_types;
+            // Nested types of nested types, to any depth
+            self.fill_nested_types_recursive(metadata, config, &mut nested_type.nested_types);
+        }
     }
 
-    pub fn get_context_root_tag(&self, ty: impl Into<TypeTag>) -> TypeTag {
+    /// The tag of the context a type is written in
+    /// Nested types are written with their outermost declaring type
+    pub fn get_context_root_tag(&self, metadata: &Metadata, ty: impl Into<TypeTag>) -> TypeTag {
         let tag = ty.into();
-        self.alias_context
-            .get(&tag)
-            .cloned()
-            // .map(|t| self.get_context_root_tag(*t))
-            .unwrap_or(tag)
+        if let Some(alias) = self.alias_context.get(&tag) {
+            return *alias;
+        }
+
+        // Not aliased until the root is filled, but the root is known from the metadata
+        match tag {
+            TypeTag::TypeDefinition(mut tdi) => {
+                while let Some(parent) = metadata.child_to_parent_map.get(&tdi) {
+                    tdi = parent.tdi;
+                }
+                TypeTag::TypeDefinition(tdi)
+            }
+            _ => tag,
+        }
     }
 
     pub fn make_from(
@@ -348,12 +510,9 @@ impl CppContextCollection {
         ty: impl Into<TypeTag>,
     ) -> &mut CppContext {
         let type_tag = ty.into();
-        let context_tag = self.get_context_root_tag(type_tag);
-
-        if self.filling_types.contains(&context_tag) {
-            panic!("Currently filling type {context_tag:?}, cannot fill")
-        }
+        let context_tag = self.get_context_root_tag(metadata, type_tag);
 
+        // Types being filled are copies, so looking them up meanwhile is fine
         self.all_contexts.entry(context_tag).or_insert_with(|| {
             let tdi = CppType::get_tag_tdi(context_tag);
             CppContext::make(metadata, config, tdi, context_tag)
@@ -367,7 +526,7 @@ impl CppContextCollection {
         ty: impl Into<TypeTag>,
     ) -> Option<&mut CppType> {
         let tag = ty.into();
-        let context_root_tag = self.get_context_root_tag(tag);
+        let context_root_tag = self.get_context_root_tag(metadata, tag);
         let context = self.make_from(metadata, config, context_root_tag);
 
         context.get_cpp_type_recursive_mut(context_root_tag, tag)
@@ -385,7 +544,140 @@ impl CppContextCollection {
             alias_context: Default::default(),
         }
     }
-    pub fn get(&self) -> &HashMap<TypeTag, CppContext> {
+    pub fn get(&self) -> &BTreeMap<TypeTag, CppContext> {
         &self.all_contexts
     }
 }
+
+/// The strongly connected components of `graph`, found with Tarjan's algorithm
+/// Each component of more than one node is a cycle, along with every node on a cycle through it
+fn strongly_connected_components<T: Copy + Ord>(graph: &BTreeMap<T, Vec<T>>) -> Vec<Vec<T>> {
+    let no_edges = vec![];
+    let mut index: BTreeMap<T, usize> = BTreeMap::new();
+    let mut low_link: BTreeMap<T, usize> = BTreeMap::new();
+    let mut stack: Vec<T> = vec![];
+    let mut on_stack: BTreeSet<T> = BTreeSet::new();
+    let mut components = vec![];
+
+    for root in graph.keys() {
+        if index.contains_key(root) {
+            continue;
+        }
+
+        // Without recursion, each node visited along with the edges it has left
+        let mut path: Vec<(T, std::slice::Iter<T>)> = vec![];
+        let mut visit = Some(*root);
+
+        loop {
+            if let Some(node) = visit.take() {
+                index.insert(node, index.len());
+                low_link.insert(node, index[&node]);
+                stack.push(node);
+                on_stack.insert(node);
+                path.push((node, graph.get(&node).unwrap_or(&no_edges).iter()));
+            }
+
+            let Some((node, edges)) = path.last_mut() else {
+                break;
+            };
+            let node = *node;
+
+            match edges.next() {
+                Some(next) if !index.contains_key(next) => visit = Some(*next),
+                Some(next) => {
+                    if on_stack.contains(next) {
+                        let low = low_link[&node].min(index[next]);
+                        low_link.insert(node, low);
+                    }
+                }
+                None => {
+                    path.pop();
+                    if let Some((parent, _)) = path.last() {
+                        let low = low_link[parent].min(low_link[&node]);
+                        low_link.insert(*parent, low);
+                    }
+
+                    // The root of a component, which is everything above it on the stack
+                    if low_link[&node] == index[&node] {
+                        let start = stack.iter().rposition(|t| *t == node).unwrap();
+                        let component = stack.split_off(start);
+                        component.iter().for_each(|t| {
+                            on_stack.remove(t);
+                        });
+                        components.push(component);
+                    }
+                }
+            }
+        }
+    }
+
+    components
+}
+
+/// The nodes of `graph` that reach any of `of` through their edges, other than `of` itself
+fn dependents<T: Copy + Ord>(graph: &BTreeMap<T, Vec<T>>, of: &BTreeSet<T>) -> BTreeSet<T> {
+    let mut reverse: BTreeMap<T, Vec<T>> = BTreeMap::new();
+    for (node, edges) in graph {
+        for edge in edges {
+            reverse.entry(*edge).or_default().push(*node);
+        }
+    }
+
+    let mut found: BTreeSet<T> = BTreeSet::new();
+    let mut queue = of.iter().copied().collect_vec();
+    while let Some(node) = queue.pop() {
+        for dependent in reverse.get(&node).into_iter().flatten() {
+            if !of.contains(dependent) && found.insert(*dependent) {
+                queue.push(*dependent);
+            }
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(u32, u32)]) -> BTreeMap<u32, Vec<u32>> {
+        let mut graph: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for (from, to) in edges {
+            graph.entry(*from).or_default().push(*to);
+            graph.entry(*to).or_default();
+        }
+        graph
+    }
+
+    fn cycles(graph: &BTreeMap<u32, Vec<u32>>) -> Vec<Vec<u32>> {
+        strongly_connected_components(graph)
+            .into_iter()
+            .filter(|c| c.len() > 1)
+            .map(|c| c.into_iter().sorted().collect())
+            .collect()
+    }
+
+    #[test]
+    fn finds_a_cycle_through_a_cross_edge() {
+        // 1 -> 2 -> 3 -> 1, and 4 is on it too through 1 -> 4 -> 2
+        // found after 2 is finished, so it is a cross edge rather than one back into the path
+        let graph = graph(&[(1, 2), (2, 3), (3, 1), (1, 4), (4, 2), (5, 1), (3, 6)]);
+        assert_eq!(cycles(&graph), [[1, 2, 3, 4]]);
+    }
+
+    #[test]
+    fn finds_separate_cycles() {
+        let two = graph(&[(1, 2), (2, 1), (2, 3), (3, 4), (4, 5), (5, 3), (6, 4)]);
+        assert_eq!(cycles(&two), [vec![3, 4, 5], vec![1, 2]]);
+        let none = graph(&[(1, 2), (2, 3), (1, 3)]);
+        assert!(cycles(&none).is_empty());
+    }
+
+    #[test]
+    fn finds_dependents_of_a_cycle() {
+        let graph = graph(&[(1, 2), (2, 3), (3, 1), (4, 1), (5, 4), (3, 6), (7, 6)]);
+        let cycle: BTreeSet<u32> = [1, 2, 3].into();
+        // What the cycle depends on is fine, what depends on it is not
+        assert_eq!(dependents(&graph, &cycle), [4, 5].into());
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     io::Write,
     rc::Rc,
 };
@@ -10,17 +10,108 @@ use il2cpp_metadata_raw::TypeDefinitionIndex;
 use itertools::Itertools;
 
 use super::{
+    config::GenerationConfig,
     context::TypeTag,
-    members::{CppForwardDeclare, CppInclude, CppMember, CppTemplate},
+    members::{CppCommentedString, CppForwardDeclare, CppInclude, CppMember, CppTemplate},
+    profile::RuntimeProfile,
     writer::Writable,
 };
 
 #[derive(Debug, Clone, Default)]
 pub struct CppTypeRequirements {
-    pub forward_declares: HashSet<(CppForwardDeclare, CppInclude)>,
+    // Sorted, so headers are written the same every run
+    pub forward_declares: BTreeSet<(CppForwardDeclare, CppInclude)>,
 
     // Only value types or classes
-    pub required_includes: HashSet<CppInclude>,
+    pub required_includes: BTreeSet<CppInclude>,
+
+    // Headers the method bodies need, included once every definition is
+    pub impl_includes: BTreeSet<CppInclude>,
+
+    // Other types this one names, for finding include cycles between contexts
+    pub dependencies: BTreeMap<TypeTag, DependencyKind>,
+}
+
+/// How much of another type a type needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DependencyKind {
+    /// Only named, e.g. as a reference or in a declaration, so a forward declare is enough
+    Forward,
+    /// Inherited, stored inline or nested in it, so its definition has to be included
+    Complete,
+}
+
+/// Names declared in a type, so members don't collide once they are C++
+#[derive(Debug, Clone, Default)]
+pub struct CppSymbolTable {
+    // Fields, properties, nested types etc. which can't be overloaded
+    symbols: HashSet<String>,
+    // Method name to the parameter signatures of its overloads
+    methods: HashMap<String, HashSet<String>>,
+    // Method definition index to its reserved name
+    pub method_names: HashMap<u32, String>,
+}
+
+impl CppSymbolTable {
+    fn is_free(&self, name: &str, signature: Option<&str>) -> bool {
+        if self.symbols.contains(name) {
+            return false;
+        }
+
+        match signature {
+            Some(signature) => !self
+                .methods
+                .get(name)
+                .is_some_and(|overloads| overloads.contains(signature)),
+            None => !self.methods.contains_key(name),
+        }
+    }
+
+    /// `name`, then `name_1`, `name_2` and so on
+    /// A trailing underscore isn't doubled, double underscores are reserved in C++
+    fn candidates(name: &str) -> impl Iterator<Item = String> + '_ {
+        let stem = name.trim_end_matches('_');
+        std::iter::once(name.to_string()).chain((1..).map(move |n| format!("{stem}_{n}")))
+    }
+
+    /// Reserves `name`, or the first free `name_N` if it is taken
+    pub fn reserve(&mut self, name: &str, signature: Option<&str>) -> String {
+        let unique = Self::candidates(name)
+            .find(|candidate| self.is_free(candidate, signature))
+            .unwrap();
+
+        match signature {
+            Some(signature) => {
+                self.methods
+                    .entry(unique.clone())
+                    .or_default()
+                    .insert(signature.to_string());
+            }
+            None => {
+                self.symbols.insert(unique.clone());
+            }
+        }
+
+        unique
+    }
+
+    /// Reserves `name` followed by each suffix, renaming them together
+    pub fn reserve_group(&mut self, name: &str, suffixes: &[&str]) -> String {
+        let unique = Self::candidates(name)
+            .find(|candidate| {
+                suffixes.iter().all(|suffix| {
+                    !(candidate.ends_with('_') && suffix.starts_with('_'))
+                        && self.is_free(&format!("{candidate}{suffix}"), None)
+                })
+            })
+            .unwrap();
+
+        for suffix in suffixes {
+            self.symbols.insert(format!("{unique}{suffix}"));
+        }
+
+        unique
+    }
 }
 
 // Represents all of the information necessary for a C++ TYPE!
@@ -45,35 +136,70 @@ pub struct CppType {
     /// Move to CsType/CppType?
     pub nonmember_implementations: Vec<Rc<dyn Writable>>,
     pub nonmember_declarations: Vec<Rc<dyn Writable>>,
+    /// Outside of any namespace, such as specializations of templates in other namespaces
+    pub global_implementations: Vec<Rc<dyn Writable>>,
 
     pub is_value_type: bool,
+    // #pragma pack, for value types whose layout is written out exactly
+    pub packing: Option<u8>,
     pub requirements: CppTypeRequirements,
 
     pub inherit: Vec<String>,
     pub generic_args: CppTemplate, // Names of templates e.g T, TKey etc.
 
     pub nested_types: Vec<CppType>,
+    pub symbols: CppSymbolTable,
 }
 
 impl CppTypeRequirements {
-    pub fn need_wrapper(&mut self) {
-        self.required_includes.insert(CppInclude::new(
-            "beatsaber-hook/shared/utils/base-wrapper-type.hpp".into(),
-        ));
+    /// Records a dependency on `tag`, a complete one wins over a forward one
+    pub fn add_dependency(&mut self, tag: TypeTag, kind: DependencyKind) {
+        let existing = self.dependencies.entry(tag).or_insert(kind);
+        *existing = (*existing).max(kind);
+    }
+    pub fn need_wrapper(&mut self, config: &GenerationConfig) {
+        self.required_includes
+            .insert(CppInclude::new(config.wrapper_headers.wrapper_type.clone()));
     }
     pub fn needs_int_include(&mut self) {
         self.required_includes
             .insert(CppInclude::new_system("cstdint".into()));
     }
-    pub fn needs_stringw_include(&mut self) {
-        self.required_includes.insert(CppInclude::new(
-            "beatsaber-hook/shared/utils/typedefs-string.hpp".into(),
-        ));
+    pub fn needs_stringw_include(&mut self, config: &GenerationConfig) {
+        self.required_includes
+            .insert(CppInclude::new(config.wrapper_headers.string.clone()));
+    }
+    pub fn needs_arrayw_include(&mut self, config: &GenerationConfig) {
+        self.required_includes
+            .insert(CppInclude::new(config.wrapper_headers.array.clone()));
+    }
+    pub fn needs_type_traits_include(&mut self) {
+        self.required_includes
+            .insert(CppInclude::new_system("type_traits".into()));
+    }
+    pub fn needs_cstddef_include(&mut self) {
+        self.required_includes
+            .insert(CppInclude::new_system("cstddef".into()));
     }
-    pub fn needs_arrayw_include(&mut self) {
-        self.required_includes.insert(CppInclude::new(
-            "beatsaber-hook/shared/utils/typedefs-array".into(),
-        ));
+    pub fn needs_utility_include(&mut self) {
+        self.required_includes
+            .insert(CppInclude::new_system("utility".into()));
+    }
+    pub fn needs_functional_include(&mut self) {
+        self.required_includes
+            .insert(CppInclude::new_system("functional".into()));
+    }
+    pub fn needs_runtime_include(&mut self, config: &GenerationConfig) {
+        self.required_includes
+            .insert(CppInclude::new(config.wrapper_headers.runtime.clone()));
+        // il2cpp-api bodies check types and compare names
+        // and rethrow managed exceptions as the Il2CppExceptionWrapper of the object internals
+        if config.profile == RuntimeProfile::Il2cppApi {
+            self.need_wrapper(config);
+            self.needs_type_traits_include();
+            self.required_includes
+                .insert(CppInclude::new_system("string_view".into()));
+        }
     }
 }
 
@@ -94,13 +220,33 @@ impl CppType {
         &self.cpp_name
     }
 
-    pub fn nested_types_flattened(&self) -> HashMap<TypeTag, &CppType> {
+    /// Declarations and the definition must agree on it, or compilers warn about mismatched tags
+    pub fn keyword(&self) -> &'static str {
+        Self::keyword_for(self.is_value_type)
+    }
+
+    pub fn keyword_for(is_value_type: bool) -> &'static str {
+        match is_value_type {
+            true => "struct",
+            false => "class",
+        }
+    }
+
+    pub fn nested_types_flattened(&self) -> BTreeMap<TypeTag, &CppType> {
         self.nested_types
             .iter()
             .flat_map(|n| n.nested_types_flattened())
             .chain(self.nested_types.iter().map(|n| (n.self_tag, n)))
             .collect()
     }
+    /// Calls `f` with this type and each of its nested types, at any depth
+    pub fn for_each_type_mut(&mut self, f: &mut impl FnMut(&mut CppType)) {
+        f(self);
+        self.nested_types
+            .iter_mut()
+            .for_each(|n| n.for_each_type_mut(f));
+    }
+
     pub fn get_nested_type_mut(&mut self, into_tag: impl Into<TypeTag>) -> Option<&mut CppType> {
         let tag = into_tag.into();
 
@@ -114,25 +260,106 @@ impl CppType {
         })
     }
 
+    /// Gives a member a C++ name that doesn't collide with the others in this type
+    /// `signature` is only given for methods, which may overload each other
+    pub fn reserve_symbol(&mut self, name: &str, signature: Option<&str>) -> String {
+        let unique = self.symbols.reserve(name, signature);
+        self.report_rename(name, &unique);
+        unique
+    }
+
+    /// Like `reserve_symbol`, for members declaring `name` followed by each suffix
+    pub fn reserve_symbol_group(&mut self, name: &str, suffixes: &[&str]) -> String {
+        let unique = self.symbols.reserve_group(name, suffixes);
+        self.report_rename(name, &unique);
+        unique
+    }
+
+    fn report_rename(&mut self, name: &str, unique: &str) {
+        if unique != name {
+            println!(
+                "Renamed {}::{name} to {unique} to avoid a collision",
+                self.formatted_complete_cpp_name()
+            );
+            self.declarations
+                .push(CppMember::Comment(CppCommentedString {
+                    data: "".to_string(),
+                    comment: Some(format!("{name} renamed to {unique} to avoid a collision")),
+                }));
+        }
+    }
+
     pub fn formatted_complete_cpp_name(&self) -> String {
         // We found a valid type that we have defined for this idx!
         // TODO: We should convert it here.
         // Ex, if it is a generic, convert it to a template specialization
         // If it is a normal type, handle it accordingly, etc.
-        match &self.parent_ty_cpp_name {
-            Some(parent_ty) => {
-                format!("{}::{parent_ty}::{}", self.cpp_namespace(), self.cpp_name())
-            }
-            None => format!("{}::{}", self.cpp_namespace(), self.cpp_name()),
-        }
+        format!("{}::{}", self.cpp_namespace(), self.cpp_name())
     }
 
     pub fn write_impl(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
-        self.write_impl_internal(writer, Some(self.cpp_namespace()))
+        self.write_impl_internal(writer, Some(self.cpp_namespace()))?;
+
+        // Once the namespace is closed, including those of the nested types
+        std::iter::once(self)
+            .chain(self.nested_types_flattened().into_values())
+            .flat_map(|t| &t.global_implementations)
+            .try_for_each(|i| i.write(writer))
     }
 
     pub fn write_def(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
-        self.write_def_internal(writer, Some(self.cpp_namespace()), true)
+        // Nested types are defined at namespace scope too, and can name any type of the context
+        writer.blank_line()?;
+        writeln!(
+            writer,
+            "// Forward declaring type: {}::{}",
+            self.cpp_namespace(),
+            self.name()
+        )?;
+        let declares = std::iter::once(self)
+            .chain(self.nested_types_flattened().into_values())
+            .map(CppForwardDeclare::from_cpp_type)
+            .sorted()
+            .collect_vec();
+        CppForwardDeclare::write_grouped(&declares, writer)?;
+
+        self.write_def_nested(writer)
+    }
+
+    /// Defines the nested types first, so the type can store them and name them with aliases
+    fn write_def_nested(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
+        self.nested_types
+            .iter()
+            .try_for_each(|n| n.write_def_nested(writer))?;
+        self.write_def_internal(writer, Some(self.cpp_namespace()))
+    }
+
+    /// Names a nested type as C# does, inside the type it is nested in
+    /// Its first generic parameters are the declaring type's, which are in scope already
+    fn write_nested_alias(
+        &self,
+        writer: &mut super::writer::CppWriter,
+        declaring_args: &CppTemplate,
+    ) -> color_eyre::Result<()> {
+        CppTemplate {
+            names: self
+                .generic_args
+                .names
+                .iter()
+                .skip(declaring_args.names.len())
+                .cloned()
+                .collect(),
+            constraints: Default::default(),
+        }
+        .write(writer)?;
+        writeln!(
+            writer,
+            "using {} = {}{};",
+            self.name(),
+            self.formatted_complete_cpp_name(),
+            self.generic_args.as_args()
+        )?;
+        Ok(())
     }
 
     pub fn write_impl_internal(
@@ -142,6 +369,7 @@ impl CppType {
     ) -> color_eyre::Result<()> {
         if let Some(namespace) = namespace {
             writeln!(writer, "namespace {} {{", namespace)?;
+            writer.indent();
         }
         // Write all declarations within the type here
         self.implementations
@@ -157,6 +385,7 @@ impl CppType {
             .try_for_each(|n| n.write_impl_internal(writer, None))?;
 
         if let Some(namespace) = namespace {
+            writer.dedent();
             writeln!(writer, "}} // end namespace {}", namespace)?;
         }
 
@@ -167,35 +396,22 @@ impl CppType {
         &self,
         writer: &mut super::writer::CppWriter,
         namespace: Option<&str>,
-        fd: bool,
     ) -> color_eyre::Result<()> {
+        // Types are separated from whatever came before them
+        writer.blank_line()?;
         self.prefix_comments.iter().for_each(|pc| {
             writeln!(writer, "// {pc}")
                 .context("Prefix comment")
                 .unwrap();
         });
 
-        // forward declare self
-        {
-            if fd {
-                writeln!(
-                    writer,
-                    "// Forward declaring type: {}::{}",
-                    namespace.unwrap_or(""),
-                    self.name()
-                )?;
-            }
-
-            if let Some(n) = &namespace {
-                writeln!(writer, "namespace {n} {{",)?;
-                writer.indent();
-            }
+        if let Some(n) = &namespace {
+            writeln!(writer, "namespace {n} {{",)?;
+            writer.indent();
+        }
 
-            if fd {
-                // template<...>
-                self.generic_args.write(writer)?;
-                writeln!(writer, "struct {};", self.name())?;
-            }
+        if let Some(packing) = self.packing {
+            writeln!(writer, "#pragma pack(push, {packing})")?;
         }
 
         // Write type definition
@@ -203,10 +419,11 @@ impl CppType {
         writeln!(writer, "// Is value type: {}", self.is_value_type)?;
         // Type definition plus inherit lines
         match self.inherit.is_empty() {
-            true => writeln!(writer, "struct {} {{", self.cpp_name())?,
+            true => writeln!(writer, "{} {} {{", self.keyword(), self.cpp_name())?,
             false => writeln!(
                 writer,
-                "struct {} : {} {{",
+                "{} {} : {} {{",
+                self.keyword(),
                 self.cpp_name(),
                 self.inherit
                     .iter()
@@ -214,20 +431,17 @@ impl CppType {
                     .join(", ")
             )?,
         }
+        // Members are public either way
+        if !self.is_value_type {
+            writeln!(writer, "public:")?;
+        }
 
         writer.indent();
-
-        self.nested_types.iter().try_for_each(|n| {
-            writeln!(
-                writer,
-                "// Forward declare nested type\nstruct {};",
-                n.cpp_name
-            )
-        })?;
+        self.generic_args.write_constraints(writer)?;
 
         self.nested_types
             .iter()
-            .try_for_each(|n| n.write_def_internal(writer, None, false))?;
+            .try_for_each(|n| n.write_nested_alias(writer, &self.generic_args))?;
         // Write all declarations within the type here
         self.declarations.iter().for_each(|d| {
             d.write(writer).unwrap();
@@ -237,6 +451,10 @@ impl CppType {
         writer.dedent();
         writeln!(writer, "}};")?;
 
+        if self.packing.is_some() {
+            writeln!(writer, "#pragma pack(pop)")?;
+        }
+
         // NON MEMBER DECLARATIONS
         self.nonmember_declarations
             .iter()
@@ -251,3 +469,68 @@ impl CppType {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_repeated_names_in_order() {
+        let mut symbols = CppSymbolTable::default();
+        assert_eq!(symbols.reserve("Foo", None), "Foo");
+        assert_eq!(symbols.reserve("Foo", None), "Foo_1");
+        assert_eq!(symbols.reserve("Foo", None), "Foo_2");
+        // A name that is already one of the renames
+        assert_eq!(symbols.reserve("Foo_1", None), "Foo_1_1");
+    }
+
+    #[test]
+    fn renames_the_same_way_every_time() {
+        let reserve_all = || {
+            let mut symbols = CppSymbolTable::default();
+            ["a", "b", "a", "a_1", "b", "a"]
+                .iter()
+                .map(|n| symbols.reserve(n, None))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(reserve_all(), reserve_all());
+        assert_eq!(reserve_all(), ["a", "b", "a_1", "a_1_1", "b_1", "a_2"]);
+    }
+
+    #[test]
+    fn keeps_keyword_renames_apart() {
+        // `delete` is renamed to `delete_` before it gets here, so it can meet a real `delete_`
+        let mut symbols = CppSymbolTable::default();
+        assert_eq!(symbols.reserve("delete_", None), "delete_");
+        assert_eq!(symbols.reserve("delete_", None), "delete_1");
+        assert_eq!(symbols.reserve("delete_", Some("()")), "delete_2");
+    }
+
+    #[test]
+    fn overloads_share_a_name() {
+        let mut symbols = CppSymbolTable::default();
+        assert_eq!(symbols.reserve("Get", Some("int<0>")), "Get");
+        assert_eq!(symbols.reserve("Get", Some("float<0>")), "Get");
+        assert_eq!(symbols.reserve("Get", Some("int<0>")), "Get_1");
+        // Fields can't be overloaded with methods
+        assert_eq!(symbols.reserve("Get", None), "Get_2");
+        assert_eq!(symbols.reserve("Value", None), "Value");
+        assert_eq!(symbols.reserve("Value", Some("<0>")), "Value_1");
+    }
+
+    #[test]
+    fn renames_groups_together() {
+        let suffixes = ["_add", "_remove", "_raise"];
+        let mut symbols = CppSymbolTable::default();
+        assert_eq!(symbols.reserve_group("Changed", &suffixes), "Changed");
+        assert_eq!(symbols.reserve_group("Changed", &suffixes), "Changed_1");
+
+        // One taken name moves the whole group
+        assert_eq!(symbols.reserve("Moved_remove", None), "Moved_remove");
+        assert_eq!(symbols.reserve_group("Moved", &suffixes), "Moved_1");
+        assert_eq!(symbols.reserve("Moved_1_raise", None), "Moved_1_raise_1");
+
+        // Without making a double underscore
+        assert_eq!(symbols.reserve_group("event_", &suffixes), "event_1");
+    }
+}
@@ -1,28 +1,34 @@
 use std::{
     collections::HashMap,
     io::{Cursor, Read},
+    ops::Range,
     rc::Rc,
 };
 
 use byteorder::{LittleEndian, ReadBytesExt};
 use il2cpp_binary::{Type, TypeData, TypeEnum};
-use il2cpp_metadata_raw::{Il2CppGenericParameter, TypeDefinitionIndex};
+use il2cpp_metadata_raw::TypeDefinitionIndex;
 use itertools::Itertools;
 
 use super::{
     config::GenerationConfig,
     constants::{
         MethodDefintionExtensions, TypeDefinitionExtensions, TypeExtentions,
-        TYPE_ATTRIBUTE_INTERFACE,
+        GENERIC_PARAMETER_ATTRIBUTE_DEFAULT_CONSTRUCTOR_CONSTRAINT,
+        GENERIC_PARAMETER_ATTRIBUTE_NOT_NULLABLE_VALUE_TYPE_CONSTRAINT,
+        GENERIC_PARAMETER_ATTRIBUTE_REFERENCE_TYPE_CONSTRAINT,
     },
     context::{CppContextCollection, TypeTag},
-    cpp_type::CppType,
+    cpp_type::{CppType, DependencyKind},
     members::{
-        CppCommentedString, CppConstructorDecl, CppConstructorImpl, CppField, CppForwardDeclare,
-        CppInclude, CppMember, CppMethodData, CppMethodDecl, CppMethodImpl, CppMethodSizeStruct,
-        CppParam, CppProperty, CppTemplate,
+        CppClassInstantiation, CppClassLookup, CppCommentedString, CppConstructorDecl,
+        CppConstructorImpl, CppDelegateHelpers, CppEnumConstant, CppEnumOperators, CppEvent,
+        CppField, CppForwardDeclare, CppInclude, CppInterfaceConversions, CppInterfaceWrapper,
+        CppMember, CppMethodData, CppMethodDecl, CppMethodImpl, CppMethodSizeStruct,
+        CppNestedUnion, CppPadding, CppParam, CppProperty, CppTemplate,
     },
-    metadata::Metadata,
+    metadata::{Metadata, PointerSize},
+    profile::{ReturnKind, RuntimeProfile},
 };
 
 type Endian = LittleEndian;
@@ -39,27 +45,110 @@ pub trait CSType: Sized {
         }
     }
 
+    /// The name of a type within its namespace, with its generic parameters
+    /// e.g. `List_1_Enumerator<T>`
     fn parent_joined_cpp_name(
         metadata: &Metadata,
         config: &GenerationConfig,
         tdi: TypeDefinitionIndex,
     ) -> String {
-        let parent = metadata.child_to_parent_map.get(&tdi);
-        let ty = metadata
+        Self::joined_cpp_name_with_args(
+            metadata,
+            config,
+            tdi,
+            &Self::generic_parameter_names(
+                metadata,
+                config,
+                Self::generic_parameters(metadata, tdi),
+            ),
+        )
+    }
+
+    /// The name of a type within its namespace, instantiated with `args`
+    /// e.g. `List_1_Enumerator<int32_t>` for `List<int>.Enumerator`
+    fn joined_cpp_name_with_args(
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        tdi: TypeDefinitionIndex,
+        args: &[String],
+    ) -> String {
+        let name = Self::hoisted_cpp_name(metadata, config, tdi);
+        match args.is_empty() {
+            true => name,
+            false => format!("{name}<{}>", args.join(",")),
+        }
+    }
+
+    /// Nested types are defined at namespace scope, named after their declaring types,
+    /// so they can be forward declared like any other type e.g. `List_1_Enumerator`
+    /// The declaring type names them with an alias, as C# does
+    fn hoisted_cpp_name(
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        tdi: TypeDefinitionIndex,
+    ) -> String {
+        config.name_cpp(&Self::nested_cs_names(metadata, tdi).join("/"))
+    }
+
+    /// The names of a type and the types it is nested in as in C# metadata, outermost first
+    /// e.g. `List`1`, `Enumerator`
+    fn nested_cs_names<'a>(metadata: &'a Metadata, tdi: TypeDefinitionIndex) -> Vec<&'a str> {
+        let mut names = match metadata.child_to_parent_map.get(&tdi) {
+            Some(parent) => Self::nested_cs_names(metadata, parent.tdi),
+            None => vec![],
+        };
+        names.push(
+            metadata
+                .metadata
+                .get_str(Self::get_type_definition(metadata, tdi).name_index)
+                .unwrap(),
+        );
+        names
+    }
+
+    /// The generic parameters of a type
+    /// Nested types repeat their declaring type's parameters first
+    fn generic_parameters(metadata: &Metadata, tdi: TypeDefinitionIndex) -> Range<u32> {
+        let ty = Self::get_type_definition(metadata, tdi);
+        metadata
             .metadata
-            .type_definitions
-            .get(tdi as usize)
-            .unwrap();
-        let self_name = metadata.metadata.get_str(ty.name_index).unwrap();
+            .generic_containers
+            .get(ty.generic_container_index as usize)
+            .map(|c| c.generic_parameter_start..c.generic_parameter_start + c.type_argc)
+            .unwrap_or_default()
+    }
 
-        match parent {
-            Some(parent_ty_cpp_name) => {
-                let parent_name =
-                    Self::parent_joined_cpp_name(metadata, config, parent_ty_cpp_name.tdi);
+    /// Whether the type, or a type it is nested in, has generic parameters
+    /// Their members can only be named from inside the template, not at namespace scope
+    fn is_open_generic(metadata: &Metadata, tdi: TypeDefinitionIndex) -> bool {
+        !Self::generic_parameters(metadata, tdi).is_empty()
+    }
 
-                format!("{parent_name}::{self_name}")
-            }
-            None => self_name.to_string(),
+    fn generic_parameter_names(
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        params: Range<u32>,
+    ) -> Vec<String> {
+        params
+            .map(|param_index| {
+                let generic_param = metadata
+                    .metadata
+                    .generic_parameters
+                    .get(param_index as usize)
+                    .unwrap();
+                config.name_cpp(metadata.metadata.get_str(generic_param.name_index).unwrap())
+            })
+            .collect()
+    }
+
+    /// Nested types have no namespace of their own, they are in their outermost declaring type's
+    fn root_namespace<'a>(metadata: &'a Metadata, tdi: TypeDefinitionIndex) -> &'a str {
+        match metadata.child_to_parent_map.get(&tdi) {
+            Some(parent) => Self::root_namespace(metadata, parent.tdi),
+            None => metadata
+                .metadata
+                .get_str(Self::get_type_definition(metadata, tdi).namespace_index)
+                .unwrap(),
         }
     }
 
@@ -86,50 +175,17 @@ pub trait CSType: Sized {
             .unwrap();
 
         // Generics
-        let generics = metadata
-            .metadata
-            .generic_containers
-            .get(t.generic_container_index as usize)
-            .map(|container| {
-                let mut generics: Vec<(&Il2CppGenericParameter, Vec<u32>)> =
-                    Vec::with_capacity(container.type_argc as usize);
-
-                for i in 0..container.type_argc {
-                    let generic_param_index = i + container.generic_parameter_start;
-                    let generic_param = metadata
-                        .metadata
-                        .generic_parameters
-                        .get(generic_param_index as usize)
-                        .unwrap();
-
-                    let mut generic_constraints: Vec<u32> =
-                        Vec::with_capacity(generic_param.constraints_count as usize);
-                    for j in 0..generic_param.constraints_count {
-                        let generic_constraint_index = j + generic_param.constraints_start;
-                        let generic_constraint = metadata
-                            .metadata
-                            .generic_parameter_constraints
-                            .get(generic_constraint_index as usize)
-                            .unwrap();
-
-                        // TODO: figure out
-                        generic_constraints.push(*generic_constraint);
-                    }
-
-                    generics.push((generic_param, generic_constraints));
-                }
-                generics
-            });
-
+        // Constraints need other types, so they are made when filling
         let cpp_template = CppTemplate {
-            names: generics
-                .unwrap_or_default()
-                .iter()
-                .map(|(g, _)| metadata.metadata.get_str(g.name_index).unwrap().to_string())
-                .collect(),
+            names: Self::generic_parameter_names(
+                metadata,
+                config,
+                Self::generic_parameters(metadata, tdi),
+            ),
+            constraints: Default::default(),
         };
 
-        let ns = metadata.metadata.get_str(t.namespace_index).unwrap();
+        let ns = Self::root_namespace(metadata, tdi);
         let name = metadata.metadata.get_str(t.name_index).unwrap();
         let mut cpptype = CppType {
             self_tag: tag_copy,
@@ -138,7 +194,7 @@ pub trait CSType: Sized {
             namespace: config.namespace_cpp(ns),
             cpp_namespace: config.namespace_cpp(ns),
             name: config.name_cpp(name),
-            cpp_name: config.name_cpp(name),
+            cpp_name: Self::hoisted_cpp_name(metadata, config, tdi),
             parent_ty_tdi: parent_pair.map(|p| p.tdi),
             parent_ty_cpp_name: parent_pair
                 .map(|p| Self::parent_joined_cpp_name(metadata, config, p.tdi)),
@@ -147,15 +203,18 @@ pub trait CSType: Sized {
             implementations: Default::default(),
             nonmember_implementations: Default::default(),
             nonmember_declarations: Default::default(),
+            global_implementations: Default::default(),
             is_value_type: t.is_value_type(),
+            packing: None,
             requirements: Default::default(),
             inherit: Default::default(),
             generic_args: cpp_template,
             nested_types: Default::default(),
+            symbols: Default::default(),
         };
 
         if t.parent_index == u32::MAX {
-            if t.flags & TYPE_ATTRIBUTE_INTERFACE == 0 {
+            if !t.is_interface() {
                 println!("Skipping type: {ns}::{name} because it has parent index: {} and is not an interface!", t.parent_index);
                 return None;
             }
@@ -170,6 +229,16 @@ pub trait CSType: Sized {
 
         cpptype.make_nested_types(metadata, config, tdi);
 
+        // Nested types claim their names before any member, for their aliases
+        for nested_name in cpptype
+            .nested_types
+            .iter()
+            .map(|n| n.name.clone())
+            .collect_vec()
+        {
+            cpptype.reserve_symbol(&nested_name, None);
+        }
+
         Some(cpptype)
     }
 
@@ -180,10 +249,24 @@ pub trait CSType: Sized {
         ctx_collection: &mut CppContextCollection,
         tdi: TypeDefinitionIndex,
     ) {
+        let t = Self::get_type_definition(metadata, tdi);
+
+        // Enums wrap their value, System.Enum has nothing useful to inherit
+        if t.is_enum_type() {
+            self.make_runtime(metadata, config, tdi);
+            self.make_enum(metadata, config, ctx_collection, tdi);
+            return;
+        }
+
         self.make_parents(metadata, config, ctx_collection, tdi);
+        self.make_runtime(metadata, config, tdi);
+        self.make_generic_constraints(metadata, config, ctx_collection, tdi);
         self.make_fields(metadata, config, ctx_collection, tdi);
         self.make_properties(metadata, config, ctx_collection, tdi);
         self.make_methods(metadata, config, ctx_collection, tdi);
+        // After methods, as they forward to the reserved method names
+        self.make_events(metadata, config, ctx_collection, tdi);
+        self.make_generic_instantiations(metadata, config, ctx_collection, tdi);
     }
 
     fn make_methods(
@@ -197,30 +280,26 @@ pub trait CSType: Sized {
         let t = Self::get_type_definition(metadata, tdi);
 
         // default ctor
-        if t.is_value_type() {
-            let mut fields: Vec<CppParam> = Vec::with_capacity(t.field_count as usize);
-            for field_index in t.field_start..t.field_start + t.field_count as u32 {
-                let field = metadata.metadata.fields.get(field_index as usize).unwrap();
-                let f_type = metadata
-                    .metadata_registration
-                    .types
-                    .get(field.type_index as usize)
-                    .unwrap();
-
-                let cpp_name =
-                    cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, f_type, false);
-
-                fields.push(CppParam {
-                    name: metadata
-                        .metadata
-                        .get_str(field.name_index)
-                        .unwrap()
-                        .to_owned(),
-                    ty: cpp_name,
-                    modifiers: "".to_string(),
-                    def_value: Some("{}".to_string()),
-                });
-            }
+        // Overlapping fields can't all be initialized, so explicit layouts go without
+        let has_union = cpp_type
+            .declarations
+            .iter()
+            .any(|d| matches!(d, CppMember::NestedUnion(_)));
+        if t.is_value_type() && !has_union {
+            // Made from the instance fields, so it uses their reserved names
+            let fields = cpp_type
+                .declarations
+                .iter()
+                .filter_map(|d| match d {
+                    CppMember::Field(f) if f.instance => Some(CppParam {
+                        name: f.cpp_name.clone(),
+                        ty: f.ty.clone(),
+                        modifiers: "".to_string(),
+                        def_value: Some("{}".to_string()),
+                    }),
+                    _ => None,
+                })
+                .collect_vec();
             cpp_type
                 .declarations
                 .push(CppMember::ConstructorImpl(CppConstructorImpl {
@@ -228,11 +307,17 @@ pub trait CSType: Sized {
                     parameters: fields.clone(),
                     is_constexpr: true,
                     template: CppTemplate::default(),
+                    method_token: 0,
+                    classof_call: cpp_type.classof_cpp_name(config),
                 }));
         }
 
         // Then, handle methods
         if t.method_count > 0 {
+            // Made from C++ through New, reserved before any method could take it
+            let delegate_new_name =
+                Self::is_delegate(metadata, t).then(|| cpp_type.reserve_symbol("New", None));
+
             // Write comment for methods
             cpp_type
                 .declarations
@@ -285,11 +370,8 @@ pub trait CSType: Sized {
                     let def_value = Self::param_default_value(metadata, param_index as u32);
 
                     m_params.push(CppParam {
-                        name: metadata
-                            .metadata
-                            .get_str(param.name_index as u32)
-                            .unwrap()
-                            .to_string(),
+                        name: config
+                            .name_cpp(metadata.metadata.get_str(param.name_index as u32).unwrap()),
                         def_value,
                         ty: param_cpp_name,
                         modifiers: if param_type.is_byref() {
@@ -320,11 +402,25 @@ pub trait CSType: Sized {
                             .unwrap();
 
                         let param_str = metadata.metadata.get_str(param.name_index).unwrap();
-                        generics.push(param_str.to_string());
+                        generics.push(config.name_cpp(param_str));
                     }
                 }
 
-                let template = CppTemplate { names: generics };
+                let constraints = match generic_container {
+                    Some(generic_container) => Self::make_template_constraints(
+                        cpp_type,
+                        metadata,
+                        config,
+                        ctx_collection,
+                        generic_container.generic_parameter_start,
+                        generic_container.type_argc,
+                    ),
+                    None => vec![],
+                };
+                let template = CppTemplate {
+                    names: generics,
+                    constraints,
+                };
 
                 // Need to include this type
                 let m_ret_cpp_type_name = cpp_type.cppify_name_il2cpp(
@@ -340,6 +436,37 @@ pub trait CSType: Sized {
                     .get(&(t.method_start + i as u32))
                     .unwrap();
 
+                // Overloads are fine as long as C++ sees different parameters
+                let m_cpp_name = cpp_type.reserve_symbol(
+                    &config.name_cpp(m_name),
+                    Some(&format!(
+                        "{}<{}>",
+                        CppParam::params_types(&m_params),
+                        template.names.len()
+                    )),
+                );
+                cpp_type
+                    .symbols
+                    .method_names
+                    .insert(t.method_start + i as u32, m_cpp_name.clone());
+
+                // Invoke has the delegate's signature
+                if m_name == "Invoke"
+                    && let Some(new_name) = &delegate_new_name
+                {
+                    cpp_type.requirements.needs_functional_include();
+                    cpp_type
+                        .declarations
+                        .push(CppMember::DelegateHelpers(CppDelegateHelpers {
+                            cpp_name: cpp_type.cpp_name().clone()
+                                + &cpp_type.generic_args.as_args(),
+                            return_type: m_ret_cpp_type_name.clone(),
+                            parameters: m_params.clone(),
+                            invoke_name: m_cpp_name.clone(),
+                            new_name: new_name.clone(),
+                        }));
+                }
+
                 if m_name == ".ctor" && !t.is_value_type() {
                     cpp_type
                         .implementations
@@ -348,6 +475,8 @@ pub trait CSType: Sized {
                             parameters: m_params.clone(),
                             is_constexpr: false,
                             template: template.clone(),
+                            method_token: method.token,
+                            classof_call: cpp_type.classof_cpp_name(config),
                         }));
                     cpp_type
                         .declarations
@@ -358,64 +487,57 @@ pub trait CSType: Sized {
                         }));
                 }
 
-                let declaring_type = metadata
-                    .metadata
-                    .type_definitions
-                    .get(method.declaring_type as usize)
-                    .unwrap();
-                let tag = TypeTag::TypeDefinition(method.declaring_type);
-                let declaring_cpp_type: Option<&CppType> = if tag == cpp_type.self_tag {
-                    Some(cpp_type)
-                } else {
-                    ctx_collection
-                        .get_cpp_type(metadata, config, tag)
-                        .map(|c| &*c) // &mut -> &
-                };
-
-                cpp_type
-                    .nonmember_implementations
-                    .push(Rc::new(CppMethodSizeStruct {
-                        ret_ty: m_ret_cpp_type_name.clone(),
-                        cpp_method_name: config.name_cpp(m_name),
-                        complete_type_name: cpp_type.formatted_complete_cpp_name(),
-                        instance: !method.is_static_method(),
-                        params: m_params.clone(),
-                        template: template.clone(),
-                        method_data: CppMethodData {
-                            addrs: method_calc.addrs,
-                            estimated_size: method_calc.estimated_size,
-                        },
-                        interface_clazz_of: declaring_cpp_type
-                            .map(|d| d.classof_cpp_name())
-                            .unwrap_or_else(|| format!("Bad stuff happened {:?}", declaring_type)),
-                        is_final: method.is_final_method(),
-                        slot: if method.slot != u16::MAX {
-                            Some(method.slot)
-                        } else {
-                            None
-                        },
-                    }));
+                // Open generics have no addresses, make_generic_instantiations handles them
+                if !Self::is_open_generic(metadata, tdi) && template.names.is_empty() {
+                    cpp_type
+                        .global_implementations
+                        .push(Rc::new(CppMethodSizeStruct {
+                            ret_ty: m_ret_cpp_type_name.clone(),
+                            cpp_method_name: m_cpp_name.clone(),
+                            cs_method_name: m_name.to_string(),
+                            complete_type_name: cpp_type.formatted_complete_cpp_name(),
+                            instance: !method.is_static_method(),
+                            params: m_params.clone(),
+                            template: template.clone(),
+                            generic_args: vec![],
+                            method_data: CppMethodData {
+                                addrs: method_calc.addrs,
+                                estimated_size: method_calc.estimated_size,
+                            },
+                        }));
+                }
                 cpp_type
                     .implementations
                     .push(CppMember::MethodImpl(CppMethodImpl {
-                        cpp_method_name: config.name_cpp(m_name),
+                        cpp_method_name: m_cpp_name.clone(),
                         cs_method_name: m_name.to_string(),
                         holder_cpp_namespaze: cpp_type.cpp_namespace().to_string(),
-                        holder_cpp_name: match &cpp_type.parent_ty_cpp_name {
-                            Some(p) => format!("{p}::{}", cpp_type.cpp_name().clone()),
-                            None => cpp_type.cpp_name().clone(),
-                        },
+                        holder_cpp_name: cpp_type.cpp_name().clone()
+                            + &cpp_type.generic_args.as_args(),
+                        holder_template: cpp_type.generic_args.clone(),
                         return_type: m_ret_cpp_type_name.clone(),
+                        return_kind: Self::return_kind(metadata, m_ret_type),
                         parameters: m_params.clone(),
                         instance: !method.is_static_method(),
                         suffix_modifiers: Default::default(),
                         prefix_modifiers: Default::default(),
                         template: template.clone(),
+                        method_token: method.token,
+                        // Value types are sealed and not objects, so they call their own methods
+                        // Generic methods are inflated per instantiation, the slot only has the definition
+                        vtable_slot: match method.slot != u16::MAX
+                            && !method.is_final_method()
+                            && !t.is_value_type()
+                            && template.names.is_empty()
+                        {
+                            true => Some(method.slot),
+                            false => None,
+                        },
                     }));
                 cpp_type
                     .declarations
                     .push(CppMember::MethodDecl(CppMethodDecl {
-                        cpp_name: config.name_cpp(m_name),
+                        cpp_name: m_cpp_name,
                         return_type: m_ret_cpp_type_name,
                         parameters: m_params,
                         instance: !method.is_static_method(),
@@ -432,6 +554,203 @@ pub trait CSType: Sized {
         }
     }
 
+    fn make_runtime(
+        &mut self,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        tdi: TypeDefinitionIndex,
+    ) {
+        let cpp_type = self.get_mut_cpp_type();
+
+        cpp_type.requirements.needs_runtime_include(config);
+
+        // Before any other member, which would be renamed instead
+        if config.profile == RuntimeProfile::Il2cppApi {
+            cpp_type.reserve_symbol("___il2cpp_class", None);
+        }
+
+        cpp_type
+            .declarations
+            .push(CppMember::ClassLookup(Self::class_lookup(metadata, tdi)));
+    }
+
+    fn class_lookup(metadata: &Metadata, tdi: TypeDefinitionIndex) -> CppClassLookup {
+        CppClassLookup {
+            image: metadata.type_image_name(tdi).to_string(),
+            namespace: Self::root_namespace(metadata, tdi).to_string(),
+            names: Self::nested_cs_names(metadata, tdi)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            generic: Self::is_open_generic(metadata, tdi),
+        }
+    }
+
+    fn make_enum(
+        &mut self,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        ctx_collection: &mut CppContextCollection,
+        tdi: TypeDefinitionIndex,
+    ) {
+        let t = Self::get_type_definition(metadata, tdi);
+
+        let fields = (t.field_start..t.field_start + t.field_count as u32)
+            .map(|field_index| {
+                let field = metadata.metadata.fields.get(field_index as usize).unwrap();
+                let f_type = metadata
+                    .metadata_registration
+                    .types
+                    .get(field.type_index as usize)
+                    .unwrap();
+                (field_index, field, f_type)
+            })
+            .collect_vec();
+
+        // value__ is the only instance field and holds the underlying type
+        let Some((_, value_field, value_type)) = fields
+            .iter()
+            .find(|(_, _, f_type)| !f_type.is_static() && !f_type.is_const())
+        else {
+            println!(
+                "Enum {} has no value__ field!",
+                metadata.type_full_name(tdi)
+            );
+            return;
+        };
+        let value_cs_name = metadata.metadata.get_str(value_field.name_index).unwrap();
+
+        let flags = Self::is_flags_enum(metadata, tdi);
+        let cpp_type = self.get_mut_cpp_type();
+        let backing_ty =
+            cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, value_type, false);
+        let enum_ty = cpp_type.cpp_name().clone() + &cpp_type.generic_args.as_args();
+        // Before the values, so these keep their names
+        // value__ is a reserved identifier in C++
+        let value_name = cpp_type.reserve_symbol(&config.name_cpp(value_cs_name), None);
+        let has_flag_name = match flags {
+            true => cpp_type.reserve_symbol("HasFlag", None),
+            false => "HasFlag".to_string(),
+        };
+
+        cpp_type
+            .declarations
+            .push(CppMember::Comment(CppCommentedString {
+                data: "".to_string(),
+                comment: Some("Enum values".to_string()),
+            }));
+
+        // The blobs are typed as the enum itself, so read them as the backing type
+        for (field_index, field, f_type) in &fields {
+            if !f_type.is_const() {
+                continue;
+            }
+
+            let Some(literal) = metadata
+                .metadata
+                .field_default_values
+                .iter()
+                .find(|f| f.field_index == *field_index)
+                .map(|def| {
+                    Self::default_value_blob(metadata, value_type.ty, def.data_index as usize)
+                })
+            else {
+                continue;
+            };
+
+            let f_name = metadata.metadata.get_str(field.name_index).unwrap();
+            let constant = CppEnumConstant {
+                name: f_name.to_owned(),
+                cpp_name: cpp_type.reserve_symbol(&config.name_cpp(f_name), None),
+                enum_ty: enum_ty.clone(),
+                holder_template: cpp_type.generic_args.clone(),
+                value: literal,
+            };
+
+            cpp_type
+                .declarations
+                .push(CppMember::EnumConstantDecl(constant.clone()));
+            cpp_type
+                .implementations
+                .push(CppMember::EnumConstantImpl(constant));
+        }
+
+        cpp_type
+            .declarations
+            .push(CppMember::Comment(CppCommentedString {
+                data: "".to_string(),
+                comment: Some("Underlying value".to_string()),
+            }));
+        cpp_type.declarations.push(CppMember::Field(CppField {
+            name: value_cs_name.to_string(),
+            cpp_name: value_name.clone(),
+            storage_name: value_name.clone(),
+            ty: backing_ty.clone(),
+            offset: 0,
+            instance: true,
+            readonly: false,
+            classof_call: cpp_type.classof_cpp_name(config),
+            literal_value: None,
+            use_wrapper: false,
+            lookup_offset: false,
+        }));
+
+        cpp_type
+            .declarations
+            .push(CppMember::ConstructorImpl(CppConstructorImpl {
+                holder_cpp_ty_name: cpp_type.cpp_name().clone(),
+                parameters: vec![CppParam {
+                    name: value_name.clone(),
+                    ty: backing_ty.clone(),
+                    modifiers: "".to_string(),
+                    def_value: Some("{}".to_string()),
+                }],
+                is_constexpr: true,
+                template: CppTemplate::default(),
+                method_token: 0,
+                classof_call: cpp_type.classof_cpp_name(config),
+            }));
+
+        cpp_type
+            .declarations
+            .push(CppMember::Comment(CppCommentedString {
+                data: "".to_string(),
+                comment: Some("Operators".to_string()),
+            }));
+        cpp_type
+            .declarations
+            .push(CppMember::EnumOperators(CppEnumOperators {
+                cpp_name: cpp_type.cpp_name().clone(),
+                backing_ty,
+                value_name,
+                flags,
+                has_flag_name,
+            }));
+    }
+
+    fn is_delegate(metadata: &Metadata, t: &il2cpp_metadata_raw::Il2CppTypeDefinition) -> bool {
+        let Some(TypeData::TypeDefinitionIndex(parent_tdi)) = metadata
+            .metadata_registration
+            .types
+            .get(t.parent_index as usize)
+            .map(|p| p.data)
+        else {
+            return false;
+        };
+
+        let parent = Self::get_type_definition(metadata, parent_tdi);
+        metadata.metadata.get_str(parent.namespace_index).unwrap() == "System"
+            && metadata.metadata.get_str(parent.name_index).unwrap() == "MulticastDelegate"
+    }
+
+    /// Whether the enum is marked [Flags], so its values are meant to be combined
+    fn is_flags_enum(metadata: &Metadata, tdi: TypeDefinitionIndex) -> bool {
+        metadata
+            .type_custom_attributes(tdi)
+            .into_iter()
+            .any(|attribute| metadata.type_full_name(attribute) == "System.FlagsAttribute")
+    }
+
     fn make_fields(
         &mut self,
         metadata: &Metadata,
@@ -441,6 +760,7 @@ pub trait CSType: Sized {
     ) {
         let cpp_type = self.get_mut_cpp_type();
         let t = Self::get_type_definition(metadata, tdi);
+        let open_generic = Self::is_open_generic(metadata, tdi);
 
         // Then, handle fields
         if t.field_count == 0 {
@@ -453,6 +773,9 @@ pub trait CSType: Sized {
                 data: "".to_string(),
                 comment: Some("Fields".to_string()),
             }));
+        // Value type instance fields are laid out once all are known
+        let mut instance_fields: Vec<(CppField, Option<u32>)> = vec![];
+
         // Then, for each field, write it out
         for i in 0..t.field_count {
             let field_index = (t.field_start + i as u32) as usize;
@@ -473,23 +796,257 @@ pub trait CSType: Sized {
 
             let _f_type_data = TypeTag::from(f_type.data);
 
+            // Fields of value types and constants are variables of their type, so it has to be complete
+            // Reference types are pointed to, except by bs-hook, which stores its wrappers by value
+            let is_inline = (t.is_value_type() || f_type.is_const())
+                && (Self::is_value_type(metadata, f_type)
+                    || config.profile == RuntimeProfile::BsHook);
             let cpp_name =
-                cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, f_type, false);
+                cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, f_type, is_inline);
 
             let def_value = Self::field_default_value(metadata, field_index as u32);
 
+            let f_cpp_name = cpp_type.reserve_symbol(&config.name_cpp(f_name), None);
+            // The wrapper of a literal moves aside for its constant
+            let f_storage_name = match def_value.is_some() && !t.is_value_type() {
+                true => cpp_type.reserve_symbol(&format!("___{f_cpp_name}"), None),
+                false => f_cpp_name.clone(),
+            };
+
             // Need to include this type
-            cpp_type.declarations.push(CppMember::Field(CppField {
+            let cpp_field = CppField {
                 name: f_name.to_owned(),
+                cpp_name: f_cpp_name,
+                storage_name: f_storage_name,
                 ty: cpp_name,
                 offset: *f_offset,
                 instance: !f_type.is_static() && !f_type.is_const(),
                 readonly: f_type.is_const(),
-                classof_call: cpp_type.classof_cpp_name(),
+                classof_call: cpp_type.classof_cpp_name(config),
                 literal_value: def_value,
                 use_wrapper: !t.is_value_type(),
+                lookup_offset: open_generic,
+            };
+
+            match t.is_value_type() && cpp_field.instance {
+                true => instance_fields.push((cpp_field, Self::type_size(metadata, f_type))),
+                false => cpp_type.declarations.push(CppMember::Field(cpp_field)),
+            }
+        }
+
+        if t.is_value_type() {
+            self.make_value_type_layout(metadata, tdi, instance_fields);
+        }
+    }
+
+    /// Writes value type fields exactly as il2cpp lays them out
+    /// Gaps become padding and overlapping fields (explicit layouts) share byte storage
+    fn make_value_type_layout(
+        &mut self,
+        metadata: &Metadata,
+        tdi: TypeDefinitionIndex,
+        mut fields: Vec<(CppField, Option<u32>)>,
+    ) {
+        let open_generic = Self::is_open_generic(metadata, tdi);
+        let cpp_type = self.get_mut_cpp_type();
+
+        // Value type offsets include the object header they'd have when boxed
+        let header_size = 2 * metadata.pointer_size.bytes();
+        fields
+            .iter_mut()
+            .for_each(|(f, _)| f.offset = f.offset.saturating_sub(header_size));
+
+        // Sizes of open generics mean nothing, so only write the fields as they are
+        let sizes = fields
+            .iter()
+            .map(|(_, size)| *size)
+            .collect::<Option<Vec<u32>>>();
+        let (Some(sizes), false) = (sizes, open_generic) else {
+            if !open_generic {
+                println!(
+                    "Not asserting the layout of {}, a field has an unknown size",
+                    cpp_type.formatted_complete_cpp_name()
+                );
+                cpp_type
+                    .declarations
+                    .push(CppMember::Comment(CppCommentedString {
+                        data: "".to_string(),
+                        comment: Some(
+                            "Layout not asserted, a field has an unknown size".to_string(),
+                        ),
+                    }));
+            }
+            cpp_type
+                .declarations
+                .extend(fields.into_iter().map(|(f, _)| CppMember::Field(f)));
+            return;
+        };
+
+        let mut fields = fields.into_iter().map(|(f, _)| f).zip(sizes).collect_vec();
+        fields.sort_by_key(|(f, _)| f.offset);
+
+        // Fields overlapping any earlier field of the group share its storage
+        let mut groups: Vec<(u32, u32, Vec<CppField>)> = vec![];
+        for (field, size) in fields {
+            match groups.last_mut() {
+                Some((_, end, group)) if field.offset < *end => {
+                    *end = (*end).max(field.offset + size);
+                    group.push(field);
+                }
+                _ => groups.push((field.offset, field.offset + size, vec![field])),
+            }
+        }
+
+        let complete_name = cpp_type.formatted_complete_cpp_name();
+        let mut layout_asserts: Vec<String> = vec![];
+        let mut layout_end = 0;
+        for (start, end, group) in groups {
+            if start > layout_end {
+                let cpp_name =
+                    cpp_type.reserve_symbol(&format!("___padding_0x{layout_end:x}"), None);
+                cpp_type.declarations.push(CppMember::Padding(CppPadding {
+                    cpp_name,
+                    size: start - layout_end,
+                }));
+            }
+            layout_end = end;
+
+            match group.len() {
+                1 => {
+                    layout_asserts.extend(group.iter().map(|f| {
+                        format!(
+                            "static_assert(offsetof({complete_name}, {}) == 0x{:x});",
+                            f.cpp_name, f.offset
+                        )
+                    }));
+                    cpp_type
+                        .declarations
+                        .extend(group.into_iter().map(CppMember::Field));
+                }
+                _ => {
+                    let storage_name =
+                        cpp_type.reserve_symbol(&format!("___storage_0x{start:x}"), None);
+                    layout_asserts.push(format!(
+                        "static_assert(offsetof({complete_name}, {storage_name}) == 0x{start:x});"
+                    ));
+                    cpp_type
+                        .declarations
+                        .push(CppMember::NestedUnion(CppNestedUnion {
+                            offset: start,
+                            size: end - start,
+                            storage_name,
+                            fields: group,
+                        }));
+                }
+            }
+        }
+
+        let size = Self::value_type_size(metadata, tdi);
+        if size > layout_end {
+            let cpp_name = cpp_type.reserve_symbol(&format!("___padding_0x{layout_end:x}"), None);
+            cpp_type.declarations.push(CppMember::Padding(CppPadding {
+                cpp_name,
+                size: size - layout_end,
             }));
         }
+        layout_asserts.push(format!(
+            "static_assert(sizeof({complete_name}) == 0x{size:x});"
+        ));
+
+        cpp_type.packing = Some(1);
+        cpp_type.requirements.needs_cstddef_include();
+        cpp_type
+            .nonmember_declarations
+            .push(Rc::new(CppCommentedString {
+                data: layout_asserts.join("\n"),
+                comment: Some("Layout".to_string()),
+            }));
+    }
+
+    /// The unboxed size of a value type
+    fn value_type_size(metadata: &Metadata, tdi: TypeDefinitionIndex) -> u32 {
+        metadata
+            .metadata_registration
+            .type_definition_sizes
+            .get(tdi as usize)
+            .unwrap()
+            .instance_size
+            - 2 * metadata.pointer_size.bytes()
+    }
+
+    /// Whether values of a type are stored inline rather than referenced
+    fn is_value_type(metadata: &Metadata, typ: &Type) -> bool {
+        match (typ.ty, typ.data) {
+            (TypeEnum::Valuetype, _) => true,
+            (TypeEnum::Genericinst, TypeData::GenericClassIndex(e)) => metadata
+                .metadata_registration
+                .generic_classes
+                .get(e)
+                .is_some_and(|gc| {
+                    Self::get_type_definition(metadata, gc.type_definition_index).is_value_type()
+                }),
+            _ => false,
+        }
+    }
+
+    /// How a method returning this type gives back its result when invoked
+    fn return_kind(metadata: &Metadata, typ: &Type) -> ReturnKind {
+        match typ.ty {
+            TypeEnum::Var | TypeEnum::Mvar => ReturnKind::GenericParameter,
+            TypeEnum::Class
+            | TypeEnum::String
+            | TypeEnum::Object
+            | TypeEnum::Szarray
+            | TypeEnum::Array => ReturnKind::Reference,
+            TypeEnum::Genericinst => match Self::is_value_type(metadata, typ) {
+                true => ReturnKind::Value,
+                false => ReturnKind::Reference,
+            },
+            // Primitives, pointers and structs are all boxed
+            _ => ReturnKind::Value,
+        }
+    }
+
+    /// The size of a field of this type, if it can be known without instantiating generics
+    fn type_size(metadata: &Metadata, typ: &Type) -> Option<u32> {
+        match typ.ty {
+            TypeEnum::Boolean | TypeEnum::I1 | TypeEnum::U1 => Some(1),
+            TypeEnum::Char | TypeEnum::I2 | TypeEnum::U2 => Some(2),
+            TypeEnum::I4 | TypeEnum::U4 | TypeEnum::R4 => Some(4),
+            TypeEnum::I8 | TypeEnum::U8 | TypeEnum::R8 => Some(8),
+            TypeEnum::I
+            | TypeEnum::U
+            | TypeEnum::Ptr
+            | TypeEnum::Fnptr
+            | TypeEnum::Class
+            | TypeEnum::String
+            | TypeEnum::Object
+            | TypeEnum::Szarray
+            | TypeEnum::Array => Some(metadata.pointer_size.bytes()),
+            TypeEnum::Valuetype => match typ.data {
+                TypeData::TypeDefinitionIndex(tdi) => Some(Self::value_type_size(metadata, tdi)),
+                _ => None,
+            },
+            TypeEnum::Genericinst => match typ.data {
+                TypeData::GenericClassIndex(e) => {
+                    let generic_class = metadata
+                        .metadata_registration
+                        .generic_classes
+                        .get(e)
+                        .unwrap();
+                    let generic_type_def =
+                        Self::get_type_definition(metadata, generic_class.type_definition_index);
+
+                    // Generic value types are as big as their instantiation
+                    match generic_type_def.is_value_type() {
+                        true => None,
+                        false => Some(metadata.pointer_size.bytes()),
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        }
     }
 
     fn make_parents(
@@ -499,18 +1056,21 @@ pub trait CSType: Sized {
         ctx_collection: &mut CppContextCollection,
         tdi: TypeDefinitionIndex,
     ) {
-        let cpp_type = self.get_mut_cpp_type();
         let t = metadata
             .metadata
             .type_definitions
             .get(tdi as usize)
             .unwrap();
+        let boxed_classof = t.is_value_type().then(|| self.classof_cpp_name(config));
+        let cpp_type = self.get_mut_cpp_type();
         let ns = metadata.metadata.get_str(t.namespace_index).unwrap();
         let name = metadata.metadata.get_str(t.name_index).unwrap();
 
-        if t.parent_index == u32::MAX {
-            // TYPE_ATTRIBUTE_INTERFACE = 0x00000020
-            if t.flags & TYPE_ATTRIBUTE_INTERFACE == 0 {
+        // Value types are laid out as they are in memory, System.ValueType adds nothing
+        if t.is_value_type() {
+            // Their interfaces are still converted to below
+        } else if t.parent_index == u32::MAX {
+            if !t.is_interface() {
                 println!("Skipping type: {ns}::{name} because it has parent index: {} and is not an interface!", t.parent_index);
             }
         } else if let Some(parent_type) = metadata
@@ -526,19 +1086,110 @@ pub trait CSType: Sized {
             panic!("NO PARENT! But valid index found: {}", t.parent_index);
         }
 
-        for interface_index in t.interfaces_start..t.interfaces_start + (t.interfaces_count as u32)
-        {
-            let int_ty = metadata
-                .metadata_registration
-                .types
-                .get(interface_index as usize)
-                .unwrap();
+        // Interfaces wrap the same instance rather than being C++ bases
+        if t.is_interface()
+            && let Some((base, _)) = config.profile.interface_wrapper(cpp_type.cpp_name())
+        {
+            cpp_type.requirements.need_wrapper(config);
+            cpp_type.inherit.push(base);
+            cpp_type
+                .declarations
+                .push(CppMember::InterfaceWrapper(CppInterfaceWrapper {
+                    cpp_name: cpp_type.cpp_name().clone(),
+                }));
+        }
+
+        // The conversions of a type hide its parents', so they convert to the parents' interfaces too
+        // A type without interfaces of its own uses its parent's
+        if t.interfaces_count == 0 {
+            return;
+        }
+
+        let mut interfaces: Vec<String> = vec![];
+        // Interfaces of a generic parent are in terms of its parameters, named as its arguments here
+        let mut ancestor = Some((tdi, HashMap::new()));
+        while let Some((ancestor_tdi, substitutions)) = ancestor {
+            let ancestor_t = Self::get_type_definition(metadata, ancestor_tdi);
+            for interface_index in ancestor_t.interfaces_start
+                ..ancestor_t.interfaces_start + (ancestor_t.interfaces_count as u32)
+            {
+                let type_index = metadata
+                    .metadata
+                    .interfaces
+                    .get(interface_index as usize)
+                    .unwrap();
+                let int_ty = metadata
+                    .metadata_registration
+                    .types
+                    .get(*type_index as usize)
+                    .unwrap();
 
-            // We have a parent, lets do something with it
-            let inherit_type =
-                cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, int_ty, true);
-            cpp_type.inherit.push(inherit_type);
+                interfaces.push(cpp_type.cppify_name_il2cpp_substituted(
+                    ctx_collection,
+                    metadata,
+                    config,
+                    int_ty,
+                    true,
+                    &substitutions,
+                ));
+            }
+
+            let parent_type = match ancestor_t.parent_index {
+                u32::MAX => None,
+                parent_index => metadata
+                    .metadata_registration
+                    .types
+                    .get(parent_index as usize),
+            };
+            ancestor = match parent_type.map(|p| p.data) {
+                Some(TypeData::TypeDefinitionIndex(parent_tdi)) => {
+                    Some((parent_tdi, HashMap::new()))
+                }
+                Some(TypeData::GenericClassIndex(e)) => {
+                    let generic_class = metadata
+                        .metadata_registration
+                        .generic_classes
+                        .get(e)
+                        .unwrap();
+                    let parent_tdi = generic_class.type_definition_index;
+                    let parameter_start = Self::generic_parameters(metadata, parent_tdi).start;
+                    let parent_substitutions = generic_class
+                        .context
+                        .class_inst_idx
+                        .and_then(|i| metadata.metadata_registration.generic_insts.get(i))
+                        .into_iter()
+                        .flat_map(|inst| &inst.types)
+                        .enumerate()
+                        .map(|(i, t)| {
+                            let arg = metadata.metadata_registration.types.get(*t).unwrap();
+                            let name = cpp_type.cppify_name_il2cpp_substituted(
+                                ctx_collection,
+                                metadata,
+                                config,
+                                arg,
+                                false,
+                                &substitutions,
+                            );
+                            (parameter_start + i as u32, name)
+                        })
+                        .collect();
+                    Some((parent_tdi, parent_substitutions))
+                }
+                _ => None,
+            };
         }
+        let interfaces = interfaces.into_iter().unique().collect_vec();
+
+        cpp_type.requirements.needs_type_traits_include();
+        // Before any other member, which would be renamed instead
+        let as_name = cpp_type.reserve_symbol("as", None);
+        cpp_type
+            .declarations
+            .push(CppMember::InterfaceConversions(CppInterfaceConversions {
+                interfaces,
+                as_name,
+                boxed_classof,
+            }));
     }
 
     fn make_nested_types(
@@ -664,11 +1315,14 @@ pub trait CSType: Sized {
                 }
             };
 
+            let p_cpp_name_reserved = cpp_type.reserve_symbol(&config.name_cpp(p_name), None);
+
             // Need to include this type
             cpp_type.declarations.push(CppMember::Property(CppProperty {
                 name: p_name.to_owned(),
+                cpp_name: p_cpp_name_reserved,
                 ty: p_cpp_name.clone(),
-                classof_call: cpp_type.classof_cpp_name(),
+                classof_call: cpp_type.classof_cpp_name(config),
                 setter: p_setter.map(|_| method_map(prop.set)),
                 getter: p_getter.map(|_| method_map(prop.get)),
                 abstr: p_getter.or(p_setter).unwrap().is_abstract_method(),
@@ -677,6 +1331,570 @@ pub trait CSType: Sized {
         }
     }
 
+    fn make_events(
+        &mut self,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        ctx_collection: &mut CppContextCollection,
+        tdi: u32,
+    ) {
+        let cpp_type = self.get_mut_cpp_type();
+        let t = Self::get_type_definition(metadata, tdi);
+
+        // Then, handle events
+        if t.event_count == 0 {
+            return;
+        }
+        // Write comment for events
+        cpp_type
+            .declarations
+            .push(CppMember::Comment(CppCommentedString {
+                data: "".to_string(),
+                comment: Some("Events".to_string()),
+            }));
+        for i in 0..t.event_count {
+            let event = metadata
+                .metadata
+                .events
+                .get((t.event_start + i as u32) as usize)
+                .unwrap();
+            let e_name = metadata.metadata.get_str(event.name_index).unwrap();
+
+            // Like properties, the accessors are relative to the type's methods
+            let accessor = |m: u32| (m != u32::MAX).then_some(t.method_start + m);
+            let e_add = accessor(event.add);
+            let e_remove = accessor(event.remove);
+            let e_raise = accessor(event.raise);
+
+            let Some(any_accessor) = e_add
+                .or(e_remove)
+                .or(e_raise)
+                .and_then(|m| metadata.metadata.methods.get(m as usize))
+            else {
+                println!("Skipping event {e_name} because it has no accessors");
+                continue;
+            };
+
+            let e_type = metadata
+                .metadata_registration
+                .types
+                .get(event.type_index as usize)
+                .unwrap();
+
+            let e_cpp_name =
+                cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, e_type, false);
+
+            if e_raise.is_some() {
+                cpp_type.requirements.needs_utility_include();
+            }
+
+            // Forward to the methods by the names they were given
+            let method_name = |m: u32| cpp_type.symbols.method_names.get(&m).cloned();
+            let add = e_add.and_then(method_name);
+            let remove = e_remove.and_then(method_name);
+            let raise = e_raise.and_then(method_name);
+
+            let e_cpp_name_reserved = cpp_type
+                .reserve_symbol_group(&config.name_cpp(e_name), &["_add", "_remove", "_raise"]);
+
+            cpp_type.declarations.push(CppMember::Event(CppEvent {
+                name: e_name.to_owned(),
+                cpp_name: e_cpp_name_reserved,
+                ty: e_cpp_name,
+                add,
+                remove,
+                raise,
+                instance: !any_accessor.is_static_method(),
+            }));
+        }
+    }
+
+    fn make_generic_constraints(
+        &mut self,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        ctx_collection: &mut CppContextCollection,
+        tdi: TypeDefinitionIndex,
+    ) {
+        let t = Self::get_type_definition(metadata, tdi);
+        let Some(container) = metadata
+            .metadata
+            .generic_containers
+            .get(t.generic_container_index as usize)
+        else {
+            return;
+        };
+
+        let cpp_type = self.get_mut_cpp_type();
+        cpp_type.generic_args.constraints = Self::make_template_constraints(
+            cpp_type,
+            metadata,
+            config,
+            ctx_collection,
+            container.generic_parameter_start,
+            container.type_argc,
+        );
+    }
+
+    /// Checks for the C# constraints of generic parameters, as (condition, message)
+    /// Misusing a generic then fails with the constraint that was broken
+    fn make_template_constraints(
+        cpp_type: &mut CppType,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        ctx_collection: &mut CppContextCollection,
+        parameter_start: u32,
+        parameter_count: u32,
+    ) -> Vec<(String, String)> {
+        let mut constraints = vec![];
+
+        for param_index in parameter_start..parameter_start + parameter_count {
+            let param = metadata
+                .metadata
+                .generic_parameters
+                .get(param_index as usize)
+                .unwrap();
+            let name = config.name_cpp(metadata.metadata.get_str(param.name_index).unwrap());
+
+            if param.flags & GENERIC_PARAMETER_ATTRIBUTE_REFERENCE_TYPE_CONSTRAINT != 0 {
+                constraints.push((
+                    format!("!({})", config.profile.value_type_check(&name)),
+                    format!("{name} must be a reference type"),
+                ));
+            }
+            // struct implies new()
+            if param.flags & GENERIC_PARAMETER_ATTRIBUTE_NOT_NULLABLE_VALUE_TYPE_CONSTRAINT != 0 {
+                constraints.push((
+                    config.profile.value_type_check(&name),
+                    format!("{name} must be a value type"),
+                ));
+            } else if param.flags & GENERIC_PARAMETER_ATTRIBUTE_DEFAULT_CONSTRUCTOR_CONSTRAINT != 0
+            {
+                constraints.push((
+                    config.profile.default_constructible_check(&name),
+                    format!("{name} must have a parameterless constructor"),
+                ));
+            }
+
+            for j in 0..param.constraints_count {
+                let constraint_index = param.constraints_start as u32 + j as u32;
+                let constraint_type = metadata
+                    .metadata
+                    .generic_parameter_constraints
+                    .get(constraint_index as usize)
+                    .and_then(|ti| metadata.metadata_registration.types.get(*ti as usize))
+                    .unwrap();
+
+                // Implied by struct and class, and enums are wrappers rather than subtypes
+                let is_interface = match (constraint_type.ty, constraint_type.data) {
+                    (TypeEnum::Object, _) => continue,
+                    (TypeEnum::Class, TypeData::TypeDefinitionIndex(constraint_tdi)) => {
+                        if matches!(
+                            metadata.type_full_name(constraint_tdi).as_str(),
+                            "System.ValueType" | "System.Enum"
+                        ) {
+                            continue;
+                        }
+                        Self::get_type_definition(metadata, constraint_tdi).is_interface()
+                    }
+                    (TypeEnum::Genericinst, TypeData::GenericClassIndex(gc)) => metadata
+                        .metadata_registration
+                        .generic_classes
+                        .get(gc)
+                        .map(|gc| {
+                            Self::get_type_definition(metadata, gc.type_definition_index)
+                                .is_interface()
+                        })
+                        .unwrap_or(false),
+                    _ => false,
+                };
+
+                let base = cpp_type.cppify_name_il2cpp(
+                    ctx_collection,
+                    metadata,
+                    config,
+                    constraint_type,
+                    false,
+                );
+                if let Some(check) = config.profile.base_type_check(&name, &base, is_interface) {
+                    constraints.push((
+                        check,
+                        format!("{name} must derive from or implement {base}"),
+                    ));
+                }
+            }
+        }
+
+        if !constraints.is_empty() {
+            cpp_type.requirements.needs_type_traits_include();
+        }
+
+        constraints
+    }
+
+    /// Writes the method info of every concrete instantiation of a generic type or method,
+    /// as the open templates have no method addresses of their own
+    /// Their field offsets are looked up at runtime instead, see `CppField::lookup_offset`
+    fn make_generic_instantiations(
+        &mut self,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        ctx_collection: &mut CppContextCollection,
+        tdi: TypeDefinitionIndex,
+    ) {
+        let t = Self::get_type_definition(metadata, tdi);
+        let container = metadata
+            .metadata
+            .generic_containers
+            .get(t.generic_container_index as usize);
+
+        // Several generic classes can share the same instantiation
+        // Non generic types only have their generic methods instantiated
+        let class_insts: Vec<Option<usize>> = match container {
+            Some(_) => metadata
+                .generic_class_instantiations
+                .get(&tdi)
+                .into_iter()
+                .flatten()
+                .filter_map(|gc| {
+                    metadata
+                        .metadata_registration
+                        .generic_classes
+                        .get(*gc)
+                        .and_then(|gc| gc.context.class_inst_idx)
+                })
+                .unique()
+                .map(Some)
+                .collect(),
+            None => vec![None],
+        };
+
+        let cpp_type = self.get_mut_cpp_type();
+
+        for class_inst_idx in class_insts {
+            // Generic parameter index to the type it is instantiated with
+            let mut class_substitutions: HashMap<u32, String> = HashMap::new();
+            let complete_type_name = match (class_inst_idx, container) {
+                (Some(class_inst_idx), Some(container)) => {
+                    // Instantiations over other generic parameters are still open
+                    let Some(args) = Self::cppify_generic_inst(
+                        cpp_type,
+                        metadata,
+                        config,
+                        ctx_collection,
+                        class_inst_idx,
+                    ) else {
+                        continue;
+                    };
+
+                    class_substitutions = args
+                        .iter()
+                        .enumerate()
+                        .map(|(i, a)| (container.generic_parameter_start + i as u32, a.clone()))
+                        .collect();
+
+                    let complete_type_name = format!(
+                        "{}::{}",
+                        cpp_type.cpp_namespace(),
+                        Self::joined_cpp_name_with_args(metadata, config, tdi, &args)
+                    );
+
+                    // The definition's class is inflated with the arguments' classes
+                    if config.profile == RuntimeProfile::Il2cppApi {
+                        let argument_classes = metadata
+                            .metadata_registration
+                            .generic_insts
+                            .get(class_inst_idx)
+                            .unwrap()
+                            .types
+                            .iter()
+                            .map(|t| metadata.metadata_registration.types.get(*t).unwrap())
+                            .map(|t| {
+                                Self::il2cpp_api_classof(
+                                    cpp_type,
+                                    metadata,
+                                    config,
+                                    ctx_collection,
+                                    t,
+                                )
+                            })
+                            .collect_vec();
+                        let instantiation = CppClassInstantiation {
+                            lookup: Self::class_lookup(metadata, tdi),
+                            complete_type_name: complete_type_name.clone(),
+                            argument_classes,
+                        };
+                        cpp_type.nonmember_declarations.push(Rc::new(
+                            CppMember::ClassInstantiationDecl(instantiation.clone()),
+                        ));
+                        cpp_type
+                            .nonmember_implementations
+                            .push(Rc::new(CppMember::ClassInstantiationImpl(instantiation)));
+                    }
+
+                    complete_type_name
+                }
+                _ => cpp_type.formatted_complete_cpp_name(),
+            };
+            let class_inst = class_inst_idx.map_or(-1, |i| i as i32);
+
+            for i in 0..t.method_count {
+                let method_index = t.method_start + i as u32;
+                let method = metadata
+                    .metadata
+                    .methods
+                    .get(method_index as usize)
+                    .unwrap();
+                let m_name = metadata.metadata.get_str(method.name_index).unwrap();
+
+                if m_name == ".cctor" {
+                    continue;
+                }
+                let Some(m_cpp_name) = cpp_type.symbols.method_names.get(&method_index).cloned()
+                else {
+                    continue;
+                };
+
+                let method_container = metadata
+                    .metadata
+                    .generic_containers
+                    .get(method.generic_container_index as usize);
+
+                // Generic methods have an instantiation per method inst,
+                // the others only one for the class inst
+                let method_insts = match method_container {
+                    Some(_) => metadata
+                        .generic_method_instantiations
+                        .get(&method_index)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(c, _)| *c == class_inst)
+                        .map(|(_, m)| *m)
+                        .collect_vec(),
+                    None if class_inst != -1 => vec![-1],
+                    None => vec![],
+                };
+
+                for method_inst in method_insts {
+                    let Some(method_calc) = metadata.generic_method_calculations.get(&(
+                        method_index,
+                        class_inst,
+                        method_inst,
+                    )) else {
+                        continue;
+                    };
+
+                    // The method's own parameters only apply to this instantiation
+                    let mut substitutions = class_substitutions.clone();
+                    let generic_args = match method_container {
+                        Some(method_container) => {
+                            let Some(args) = Self::cppify_generic_inst(
+                                cpp_type,
+                                metadata,
+                                config,
+                                ctx_collection,
+                                method_inst as usize,
+                            ) else {
+                                continue;
+                            };
+
+                            substitutions.extend(args.iter().enumerate().map(|(i, a)| {
+                                (
+                                    method_container.generic_parameter_start + i as u32,
+                                    a.clone(),
+                                )
+                            }));
+                            args
+                        }
+                        None => vec![],
+                    };
+
+                    let m_ret_type = metadata
+                        .metadata_registration
+                        .types
+                        .get(method.return_type as usize)
+                        .unwrap();
+                    let ret_ty = cpp_type.cppify_name_il2cpp_substituted(
+                        ctx_collection,
+                        metadata,
+                        config,
+                        m_ret_type,
+                        false,
+                        &substitutions,
+                    );
+
+                    let params = (0..method.parameter_count)
+                        .map(|p| {
+                            let param_index = (method.parameter_start + p as u32) as usize;
+                            let param = metadata.metadata.parameters.get(param_index).unwrap();
+                            let param_type = metadata
+                                .metadata_registration
+                                .types
+                                .get(param.type_index as usize)
+                                .unwrap();
+
+                            CppParam {
+                                name: config
+                                    .name_cpp(metadata.metadata.get_str(param.name_index).unwrap()),
+                                def_value: None,
+                                ty: cpp_type.cppify_name_il2cpp_substituted(
+                                    ctx_collection,
+                                    metadata,
+                                    config,
+                                    param_type,
+                                    false,
+                                    &substitutions,
+                                ),
+                                modifiers: if param_type.is_byref() {
+                                    String::from("byref")
+                                } else {
+                                    String::from("")
+                                },
+                            }
+                        })
+                        .collect_vec();
+
+                    cpp_type
+                        .global_implementations
+                        .push(Rc::new(CppMethodSizeStruct {
+                            ret_ty,
+                            cpp_method_name: m_cpp_name.clone(),
+                            cs_method_name: m_name.to_string(),
+                            complete_type_name: complete_type_name.clone(),
+                            instance: !method.is_static_method(),
+                            params,
+                            template: CppTemplate::default(),
+                            generic_args,
+                            method_data: CppMethodData {
+                                addrs: method_calc.addrs,
+                                estimated_size: method_calc.estimated_size,
+                            },
+                        }));
+                }
+            }
+        }
+    }
+
+    /// The C++ names of a generic inst's types, None if any of them is still open
+    fn cppify_generic_inst(
+        cpp_type: &mut CppType,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        ctx_collection: &mut CppContextCollection,
+        generic_inst_idx: usize,
+    ) -> Option<Vec<String>> {
+        let generic_inst = metadata
+            .metadata_registration
+            .generic_insts
+            .get(generic_inst_idx)?;
+
+        let arg_types = generic_inst
+            .types
+            .iter()
+            .map(|t| metadata.metadata_registration.types.get(*t).unwrap())
+            .collect_vec();
+
+        if !arg_types
+            .iter()
+            .all(|a| Self::is_concrete_type(metadata, a))
+        {
+            return None;
+        }
+
+        Some(
+            arg_types
+                .iter()
+                .map(|a| {
+                    // Value types are stored inline, so the instantiation needs them complete
+                    let add_include = Self::is_value_type(metadata, a);
+                    cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, a, add_include)
+                })
+                .collect(),
+        )
+    }
+
+    /// An expression for the `Il2CppClass*` of a concrete type with il2cpp-api
+    /// Generated types look up their own, the rest are found in corlib
+    fn il2cpp_api_classof(
+        cpp_type: &mut CppType,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        ctx_collection: &mut CppContextCollection,
+        typ: &Type,
+    ) -> String {
+        let corlib = |name: &str| {
+            format!("::il2cpp_class_from_name(::il2cpp_get_corlib(), \"System\", \"{name}\")")
+        };
+
+        match typ.ty {
+            TypeEnum::Boolean => corlib("Boolean"),
+            TypeEnum::Char => corlib("Char"),
+            TypeEnum::I1 => corlib("SByte"),
+            TypeEnum::U1 => corlib("Byte"),
+            TypeEnum::I2 => corlib("Int16"),
+            TypeEnum::U2 => corlib("UInt16"),
+            TypeEnum::I4 => corlib("Int32"),
+            TypeEnum::U4 => corlib("UInt32"),
+            TypeEnum::I8 => corlib("Int64"),
+            TypeEnum::U8 => corlib("UInt64"),
+            TypeEnum::R4 => corlib("Single"),
+            TypeEnum::R8 => corlib("Double"),
+            TypeEnum::I => corlib("IntPtr"),
+            TypeEnum::U => corlib("UIntPtr"),
+            TypeEnum::String => corlib("String"),
+            TypeEnum::Object => corlib("Object"),
+            TypeEnum::Szarray => match typ.data {
+                TypeData::TypeIndex(e) => format!(
+                    "::il2cpp_array_class_get({}, 1)",
+                    Self::il2cpp_api_classof(
+                        cpp_type,
+                        metadata,
+                        config,
+                        ctx_collection,
+                        metadata.metadata_registration.types.get(e).unwrap(),
+                    )
+                ),
+                _ => panic!("Unknown type data for array {typ:?}!"),
+            },
+            _ => {
+                let cpp_name =
+                    cpp_type.cppify_name_il2cpp(ctx_collection, metadata, config, typ, false);
+                format!(
+                    "{}()",
+                    config.profile.classof_call(cpp_name.trim_end_matches('*'))
+                )
+            }
+        }
+    }
+
+    /// Whether a type is free of generic parameters
+    fn is_concrete_type(metadata: &Metadata, typ: &Type) -> bool {
+        match (typ.ty, typ.data) {
+            (TypeEnum::Var | TypeEnum::Mvar, _) => false,
+            (TypeEnum::Szarray | TypeEnum::Ptr, TypeData::TypeIndex(e)) => metadata
+                .metadata_registration
+                .types
+                .get(e)
+                .is_some_and(|t| Self::is_concrete_type(metadata, t)),
+            (TypeEnum::Genericinst, TypeData::GenericClassIndex(e)) => metadata
+                .metadata_registration
+                .generic_classes
+                .get(e)
+                .and_then(|gc| gc.context.class_inst_idx)
+                .and_then(|i| metadata.metadata_registration.generic_insts.get(i))
+                .is_some_and(|inst| {
+                    inst.types.iter().all(|t| {
+                        metadata
+                            .metadata_registration
+                            .types
+                            .get(*t)
+                            .is_some_and(|t| Self::is_concrete_type(metadata, t))
+                    })
+                }),
+            _ => true,
+        }
+    }
+
     fn default_value_blob(metadata: &Metadata, ty: TypeEnum, data_index: usize) -> String {
         let data = &metadata.metadata.field_and_parameter_default_value_data[data_index..];
 
@@ -687,13 +1905,19 @@ pub trait CSType: Sized {
             TypeEnum::I1 => cursor.read_i8().unwrap().to_string(),
             TypeEnum::I2 => cursor.read_i16::<Endian>().unwrap().to_string(),
             TypeEnum::Valuetype | TypeEnum::I4 => cursor.read_i32::<Endian>().unwrap().to_string(),
-            // TODO: We assume 64 bit
-            TypeEnum::I | TypeEnum::I8 => cursor.read_i64::<Endian>().unwrap().to_string(),
+            TypeEnum::I8 => cursor.read_i64::<Endian>().unwrap().to_string(),
+            TypeEnum::I => match metadata.pointer_size {
+                PointerSize::Bytes4 => cursor.read_i32::<Endian>().unwrap().to_string(),
+                PointerSize::Bytes8 => cursor.read_i64::<Endian>().unwrap().to_string(),
+            },
             TypeEnum::U1 => cursor.read_u8().unwrap().to_string(),
             TypeEnum::U2 => cursor.read_u16::<Endian>().unwrap().to_string(),
             TypeEnum::U4 => cursor.read_u32::<Endian>().unwrap().to_string(),
-            // TODO: We assume 64 bit
-            TypeEnum::U | TypeEnum::U8 => cursor.read_u64::<Endian>().unwrap().to_string(),
+            TypeEnum::U8 => cursor.read_u64::<Endian>().unwrap().to_string(),
+            TypeEnum::U => match metadata.pointer_size {
+                PointerSize::Bytes4 => cursor.read_u32::<Endian>().unwrap().to_string(),
+                PointerSize::Bytes8 => cursor.read_u64::<Endian>().unwrap().to_string(),
+            },
 
             // https://learn.microsoft.com/en-us/nimbusml/concepts/types
             // https://en.cppreference.com/w/cpp/types/floating-point
@@ -786,10 +2010,33 @@ pub trait CSType: Sized {
         config: &GenerationConfig,
         typ: &Type,
         add_include: bool,
+    ) -> String {
+        self.cppify_name_il2cpp_substituted(
+            ctx_collection,
+            metadata,
+            config,
+            typ,
+            add_include,
+            &HashMap::new(),
+        )
+    }
+
+    /// Like `cppify_name_il2cpp`, with generic parameters replaced by the types in `substitutions`
+    /// Instantiations know what their parameters are, keyed by generic parameter index
+    fn cppify_name_il2cpp_substituted(
+        &mut self,
+        ctx_collection: &mut CppContextCollection,
+        metadata: &Metadata,
+        config: &GenerationConfig,
+        typ: &Type,
+        add_include: bool,
+        substitutions: &HashMap<u32, String>,
     ) -> String {
         let tag = TypeTag::from(typ.data);
 
-        let context_tag = ctx_collection.get_context_root_tag(tag);
+        let context_tag = ctx_collection.get_context_root_tag(metadata, tag);
+        let own_context_tag =
+            ctx_collection.get_context_root_tag(metadata, self.get_cpp_type().self_tag);
         let cpp_type = self.get_mut_cpp_type();
         let mut nested_types: HashMap<TypeTag, String> = cpp_type
             .nested_types_flattened()
@@ -818,64 +2065,109 @@ pub trait CSType: Sized {
 
         match typ.ty {
             TypeEnum::Object => {
-                requirements.need_wrapper();
-                "::bs_hook::Il2CppWrapperType".to_string()
+                requirements.need_wrapper(config);
+                config.profile.object_type()
             }
             TypeEnum::Valuetype | TypeEnum::Class => {
+                // Parents are named as is, everything else is a reference
+                let as_reference = |cpp_name: String| match typ.ty {
+                    TypeEnum::Class if !add_include => config.profile.reference_type(&cpp_name),
+                    _ => cpp_name,
+                };
+
                 // Self
                 if tag == cpp_type.self_tag {
                     // TODO: println!("Warning! This is self referencing, handle this better in the future");
-                    return cpp_type.formatted_complete_cpp_name();
+                    return as_reference(cpp_type.formatted_complete_cpp_name());
                 }
 
                 // Skip nested classes
                 if let Some(nested) = nested_types.remove(&tag) {
-                    return nested;
+                    return as_reference(nested);
                 }
 
                 // In this case, just inherit the type
                 // But we have to:
                 // - Determine where to include it from
                 let to_incl = ctx_collection.make_from(metadata, config, typ.data);
+                let inc = CppInclude::new_context(to_incl);
+                let def_inc = CppInclude::new_context_typedef(to_incl);
+
+                // Types that could not be made have nothing to include
+                let Some(to_incl_ty) = ctx_collection.get_cpp_type(metadata, config, typ.data)
+                else {
+                    println!(
+                        "Unable to get type to include {:?}, using {} instead",
+                        typ.data,
+                        config.profile.object_type()
+                    );
+                    requirements.need_wrapper(config);
+                    return config.profile.object_type();
+                };
 
-                // - Include it
-                if add_include {
-                    requirements
-                        .required_includes
-                        .insert(CppInclude::new_context(to_incl));
+                // Types of the same context are written to the same headers
+                if context_tag == own_context_tag {
+                    return as_reference(to_incl_ty.formatted_complete_cpp_name());
                 }
-                let inc = CppInclude::new_context(to_incl);
-                let to_incl_ty = ctx_collection
-                    .get_cpp_type(metadata, config, typ.data)
-                    .unwrap_or_else(|| panic!("Unable to get type to include {:?}", typ.data));
-
-                // Forward declare it
-                if !add_include {
-                    requirements
-                        .forward_declares
-                        .insert((CppForwardDeclare::from_cpp_type(to_incl_ty), inc));
+
+                // Only parents and value types stored inline need the definition
+                // Nested types are at namespace scope, so they are forward declared like the rest
+                let complete = add_include;
+
+                requirements.add_dependency(
+                    tag,
+                    match complete {
+                        true => DependencyKind::Complete,
+                        false => DependencyKind::Forward,
+                    },
+                );
+
+                match complete {
+                    // - Include its definitions, and the rest where the method bodies are
+                    // Only definitions include definitions, so they can't include each other
+                    // through method bodies
+                    true => {
+                        requirements.required_includes.insert(def_inc);
+                        requirements.impl_includes.insert(inc);
+                    }
+                    // Forward declare it
+                    false => {
+                        requirements
+                            .forward_declares
+                            .insert((CppForwardDeclare::from_cpp_type(to_incl_ty), inc));
+                    }
                 }
 
-                to_incl_ty.formatted_complete_cpp_name()
+                as_reference(to_incl_ty.formatted_complete_cpp_name())
             }
             // TODO: MVAR and VAR
             TypeEnum::Szarray => {
-                requirements.needs_arrayw_include();
+                requirements.needs_arrayw_include(config);
 
                 let generic: String = match typ.data.into() {
                     TypeTag::Type(e) => {
                         let ty = metadata.metadata_registration.types.get(e).unwrap();
-                        self.cppify_name_il2cpp(ctx_collection, metadata, config, ty, false)
+                        self.cppify_name_il2cpp_substituted(
+                            ctx_collection,
+                            metadata,
+                            config,
+                            ty,
+                            false,
+                            substitutions,
+                        )
                     }
 
                     _ => panic!("Unknown type data for array {typ:?}!"),
                 };
 
-                format!("::ArrayW<{}>", generic)
+                config.profile.array_type(&generic)
             }
             TypeEnum::Mvar | TypeEnum::Var => match typ.data {
-                // TODO: Alias to actual generic
                 TypeData::GenericParameterIndex(index) => {
+                    if let Some(substitution) = substitutions.get(&index) {
+                        return substitution.clone();
+                    }
+
                     let generic_param = metadata
                         .metadata
                         .generic_parameters
@@ -884,7 +2176,7 @@ pub trait CSType: Sized {
 
                     let name = metadata.metadata.get_str(generic_param.name_index).unwrap();
 
-                    name.to_string()
+                    config.name_cpp(name)
                 }
                 _ => todo!(),
             },
@@ -906,7 +2198,16 @@ pub trait CSType: Sized {
                         .iter()
                         .map(|t| metadata.metadata_registration.types.get(*t).unwrap())
                         .map(|t| {
-                            self.cppify_name_il2cpp(ctx_collection, metadata, config, t, false)
+                            // A complete instantiation stores its value type arguments inline
+                            let add_include = add_include && Self::is_value_type(metadata, t);
+                            self.cppify_name_il2cpp_substituted(
+                                ctx_collection,
+                                metadata,
+                                config,
+                                t,
+                                add_include,
+                                substitutions,
+                            )
                         });
 
                     let generic_types = types.collect_vec();
@@ -927,10 +2228,21 @@ pub trait CSType: Sized {
                         metadata,
                         config,
                         generic_type,
-                        false,
+                        add_include,
                     );
 
-                    format!("{owner_name}<{}>", generic_types.join(","))
+                    // Nested types are named at namespace scope, so they take all of the arguments
+                    let instantiation = format!(
+                        "{}<{}>",
+                        owner_name.trim_end_matches('*'),
+                        generic_types.join(",")
+                    );
+
+                    // The owner is a reference, the instantiation is what gets referenced
+                    match owner_name.ends_with('*') {
+                        true => config.profile.reference_type(&instantiation),
+                        false => instantiation,
+                    }
                 }
 
                 _ => panic!("Unknown type data for generic inst {typ:?}!"),
@@ -938,13 +2250,14 @@ pub trait CSType: Sized {
             TypeEnum::I1 => "int8_t".to_string(),
             TypeEnum::I2 => "int16_t".to_string(),
             TypeEnum::I4 => "int32_t".to_string(),
-            // TODO: We assume 64 bit
-            TypeEnum::I | TypeEnum::I8 => "int64_t".to_string(),
+            TypeEnum::I8 => "int64_t".to_string(),
+            // Pointer sized, the static_assert in each header checks the target
+            TypeEnum::I => "intptr_t".to_string(),
             TypeEnum::U1 => "uint8_t".to_string(),
             TypeEnum::U2 => "uint16_t".to_string(),
             TypeEnum::U4 => "uint32_t".to_string(),
-            // TODO: We assume 64 bit
-            TypeEnum::U | TypeEnum::U8 => "uint64_t".to_string(),
+            TypeEnum::U8 => "uint64_t".to_string(),
+            TypeEnum::U => "uintptr_t".to_string(),
 
             // https://learn.microsoft.com/en-us/nimbusml/concepts/types
             // https://en.cppreference.com/w/cpp/types/floating-point
@@ -955,8 +2268,8 @@ pub trait CSType: Sized {
             TypeEnum::Boolean => "bool".to_string(),
             TypeEnum::Char => "char16_t".to_string(),
             TypeEnum::String => {
-                requirements.needs_stringw_include();
-                "::StringW".to_string()
+                requirements.needs_stringw_include(config);
+                config.profile.string_type()
             }
             TypeEnum::Ptr => "void*".to_owned(),
             // TODO: Void and the other primitives
@@ -964,11 +2277,10 @@ pub trait CSType: Sized {
         }
     }
 
-    fn classof_cpp_name(&self) -> String {
-        format!(
-            "::il2cpp_utils::il2cpp_type_check::il2cpp_no_arg_class<{}>::get",
-            self.get_cpp_type().formatted_complete_cpp_name()
-        )
+    fn classof_cpp_name(&self, config: &GenerationConfig) -> String {
+        config
+            .profile
+            .classof_call(&self.get_cpp_type().formatted_complete_cpp_name())
     }
 
     fn get_type_definition<'a>(
@@ -3,21 +3,27 @@ use itertools::Itertools;
 use super::{
     context::CppContext,
     cpp_type::CppType,
+    profile::{il2cpp_api_invoke, il2cpp_api_return, ReturnKind, RuntimeProfile},
     writer::{CppWriter, Writable},
 };
-use std::{io::Write, path::PathBuf};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Eq, Hash, PartialEq, Clone, Default, PartialOrd, Ord)]
 pub struct CppTemplate {
     pub names: Vec<String>,
+    // (condition, message) each argument is checked against
+    pub constraints: Vec<(String, String)>,
 }
 
-#[derive(Debug, Eq, Hash, PartialEq, Clone)]
+// Ordered by namespace first, so sorted forward declares are grouped by it
+#[derive(Debug, Eq, Hash, PartialEq, Clone, PartialOrd, Ord)]
 pub struct CppForwardDeclare {
-    // TODO: Make this group lots into a single namespace
-    pub is_struct: bool,
     pub namespace: Option<String>,
     pub name: String,
+    pub is_struct: bool,
     pub templates: CppTemplate, // names of template arguments, T, TArgs etc.
 }
 
@@ -30,19 +36,31 @@ pub struct CppCommentedString {
 #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct CppInclude {
     include: PathBuf,
+    // <> rather than "", found through the include paths
     system: bool,
 }
 
-
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum CppMember {
     Field(CppField),
     MethodDecl(CppMethodDecl),
     MethodImpl(CppMethodImpl),
     Property(CppProperty),
+    Event(CppEvent),
+    NestedUnion(CppNestedUnion),
     Comment(CppCommentedString),
     ConstructorDecl(CppConstructorDecl),
     ConstructorImpl(CppConstructorImpl),
+    EnumConstantDecl(CppEnumConstant),
+    EnumConstantImpl(CppEnumConstant),
+    EnumOperators(CppEnumOperators),
+    ClassLookup(CppClassLookup),
+    ClassInstantiationDecl(CppClassInstantiation),
+    ClassInstantiationImpl(CppClassInstantiation),
+    InterfaceWrapper(CppInterfaceWrapper),
+    InterfaceConversions(CppInterfaceConversions),
+    Padding(CppPadding),
+    DelegateHelpers(CppDelegateHelpers),
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -54,6 +72,7 @@ pub struct CppMethodData {
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CppMethodSizeStruct {
     pub cpp_method_name: String,
+    pub cs_method_name: String,
     pub complete_type_name: String,
     pub ret_ty: String,
     pub instance: bool,
@@ -61,14 +80,15 @@ pub struct CppMethodSizeStruct {
     pub method_data: CppMethodData,
 
     pub template: CppTemplate,
-    
-    pub interface_clazz_of: String,
-    pub is_final: bool,
-    pub slot: Option<u16>,
+    // Arguments of an instantiated generic method
+    pub generic_args: Vec<String>,
 }
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CppField {
     pub name: String,
+    pub cpp_name: String,
+    // Name of the wrapper, which differs from `cpp_name` when a literal constant takes that
+    pub storage_name: String,
     pub ty: String,
     pub offset: u32,
     pub instance: bool,
@@ -76,6 +96,8 @@ pub struct CppField {
     pub classof_call: String,
     pub literal_value: Option<String>,
     pub use_wrapper: bool,
+    // Open generics are laid out per instantiation, so `offset` is only the definition's
+    pub lookup_offset: bool,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -127,12 +149,18 @@ pub struct CppMethodImpl {
 
     pub holder_cpp_namespaze: String,
     pub holder_cpp_name: String,
+    // Template of a generic holder, written before the method's own
+    pub holder_template: CppTemplate,
 
     pub return_type: String,
+    pub return_kind: ReturnKind,
     pub parameters: Vec<CppParam>,
     pub instance: bool,
 
     pub template: CppTemplate,
+    pub method_token: u32,
+    // Virtual methods are resolved from the instance's vtable, so overrides are called
+    pub vtable_slot: Option<u16>,
     // TODO: Use bitflags to indicate these attributes
     // Holds unique of:
     // const
@@ -162,11 +190,16 @@ pub struct CppConstructorImpl {
     pub parameters: Vec<CppParam>,
     pub is_constexpr: bool,
     pub template: CppTemplate,
+
+    // The .ctor being called, unused for constexpr constructors
+    pub method_token: u32,
+    pub classof_call: String,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CppProperty {
     pub name: String,
+    pub cpp_name: String,
     pub ty: String,
     pub setter: Option<CppMethodData>,
     pub getter: Option<CppMethodData>,
@@ -174,8 +207,131 @@ pub struct CppProperty {
     pub instance: bool,
     pub classof_call: String,
 }
+
+/// Value type fields sharing storage, from an explicit layout
+/// The storage is raw bytes, so the fields are reached through accessors of the same name
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppNestedUnion {
+    // Where the storage starts, the field offsets are absolute
+    pub offset: u32,
+    pub size: u32,
+    pub storage_name: String,
+    pub fields: Vec<CppField>,
+}
+
+/// Calling a delegate like a function, and making one from C++
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppDelegateHelpers {
+    // The delegate as named inside it, e.g. `Func_2<T,TResult>`
+    pub cpp_name: String,
+    pub return_type: String,
+    pub parameters: Vec<CppParam>,
+    // The reserved names of Invoke and the New factory
+    pub invoke_name: String,
+    pub new_name: String,
+}
+
+/// Bytes of a value type no field covers
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppPadding {
+    pub cpp_name: String,
+    pub size: u32,
+}
+
+/// How the profile finds the `Il2CppClass*` of a type, if it does so itself
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppClassLookup {
+    pub image: String,
+    pub namespace: String,
+    // The outermost declaring type first, nested types are found in their declaring class
+    pub names: Vec<String>,
+    // Each instantiation has a class of its own, see `CppClassInstantiation`
+    pub generic: bool,
+}
+
+/// The class of an instantiation of a generic type, its definition's inflated with the arguments
+/// Declared with the definition, so no use of the instantiation comes before it
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppClassInstantiation {
+    pub lookup: CppClassLookup,
+    // e.g. `::System::Collections::Generic::List_1<int32_t>`
+    pub complete_type_name: String,
+    // The `Il2CppClass*` of each argument
+    pub argument_classes: Vec<String>,
+}
+
+/// The constructor of an interface, which wraps any instance implementing it
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppInterfaceWrapper {
+    pub cpp_name: String,
+}
+
+/// Conversions to the interfaces a type implements
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppInterfaceConversions {
+    pub interfaces: Vec<String>,
+    // Reserved, as `as` is a member like any other
+    pub as_name: String,
+    // Value types are boxed with their class
+    pub boxed_classof: Option<String>,
+}
+
+/// A named value of an enum, which is of the enum's own type
+/// so it is only declared in the enum and defined once the enum is complete
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppEnumConstant {
+    pub name: String,
+    pub cpp_name: String,
+    // The enum as named outside of it, e.g. `List_1_Kind<T>`
+    pub enum_ty: String,
+    // Template of a generic declaring type, written before the definition
+    pub holder_template: CppTemplate,
+    pub value: String,
+}
+
+/// Comparisons of an enum, and the bitwise operators of a [Flags] enum
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppEnumOperators {
+    pub cpp_name: String,
+    pub backing_ty: String,
+    pub value_name: String,
+    pub flags: bool,
+    // Reserved, as HasFlag is a member like any other
+    pub has_flag_name: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CppEvent {
+    pub name: String,
+    pub cpp_name: String,
+    pub ty: String,
+    // The cpp names of the accessor methods
+    pub add: Option<String>,
+    pub remove: Option<String>,
+    pub raise: Option<String>,
+    pub instance: bool,
+}
 // Writing
 
+impl CppTemplate {
+    /// Checks the constraints where the arguments are known, i.e. a definition's body
+    pub fn write_constraints(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        self.constraints
+            .iter()
+            .try_for_each(|(condition, message)| {
+                writeln!(writer, "static_assert({condition}, \"{message}\");")
+            })?;
+        Ok(())
+    }
+
+    /// The template arguments naming the template itself, e.g. `<T,U>`
+    pub fn as_args(&self) -> String {
+        match self.names.is_empty() {
+            true => "".to_string(),
+            false => format!("<{}>", self.names.join(",")),
+        }
+    }
+}
 
 impl Writable for CppTemplate {
     fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
@@ -200,56 +356,87 @@ impl CppForwardDeclare {
         Self {
             is_struct: cpp_type.is_value_type,
             namespace: Some(cpp_type.cpp_namespace().to_string()),
-            name: cpp_type.name().clone(),
+            name: cpp_type.cpp_name().clone(),
             templates: cpp_type.generic_args.clone(),
         }
     }
+
+    /// Writes sorted forward declares, one namespace block for each namespace
+    pub fn write_grouped<'a>(
+        declares: impl IntoIterator<Item = &'a CppForwardDeclare>,
+        writer: &mut CppWriter,
+    ) -> color_eyre::Result<()> {
+        for (namespace, group) in &declares.into_iter().group_by(|d| &d.namespace) {
+            if let Some(namespace) = namespace {
+                writeln!(writer, "namespace {namespace} {{")?;
+                writer.indent();
+            }
+
+            group.into_iter().try_for_each(|d| d.write(writer))?;
+
+            if namespace.is_some() {
+                writer.dedent();
+                writeln!(writer, "}}")?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
+// Only the declaration, its namespace or parent is opened by the caller
 impl Writable for CppForwardDeclare {
     fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
-        if let Some(namespace) = &self.namespace {
-            writeln!(writer, "namespace {} {{", namespace)?;
-        }
-
         self.templates.write(writer)?;
 
         writeln!(
             writer,
             "{} {};",
-            match self.is_struct {
-                true => "struct",
-                false => "class",
-            },
+            CppType::keyword_for(self.is_struct),
             self.name
         )?;
 
-        if self.namespace.is_some() {
-            writeln!(writer, "}}")?;
-        }
-
         Ok(())
     }
 }
 
 impl Writable for CppCommentedString {
     fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
-        if let Some(val) = &self.comment {
-            writeln!(writer, "// {val}")?;
+        match (&self.comment, self.data.is_empty()) {
+            // Only a comment starts a new section
+            (Some(val), true) => {
+                writer.blank_line()?;
+                writeln!(writer, "// {val}")?;
+            }
+            (Some(val), false) => {
+                writeln!(writer, "// {val}")?;
+                writer.write_code(&self.data)?;
+            }
+            (None, false) => writer.write_code(&self.data)?,
+            (None, true) => (),
         }
-        writeln!(writer, "{}", self.data)?;
         Ok(())
     }
 }
 
 impl CppInclude {
     pub fn new_context(context: &CppContext) -> Self {
+        Self::new_context_header(context, &context.fundamental_path)
+    }
+
+    /// One of a context's headers, found from the include root like any other library
+    pub fn new_context_header(context: &CppContext, header: &Path) -> Self {
         Self {
-            include: context.fundamental_path.clone(),
-            system: false,
+            include: context.relative_include_path(header),
+            system: true,
         }
     }
 
+    /// Only the definitions, for types that can't be forward declared
+    pub fn new_context_typedef(context: &CppContext) -> Self {
+        Self::new_context_header(context, &context.typedef_path)
+    }
+
     pub fn new_system(str: PathBuf) -> Self {
         Self {
             include: str,
@@ -267,16 +454,22 @@ impl CppInclude {
 
 impl Writable for CppInclude {
     fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        // Includes use / on every platform
+        let include = self
+            .include
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .join("/");
+
         if self.system {
-            writeln!(writer, "#include <{}>", self.include.to_str().unwrap())?;
+            writeln!(writer, "#include <{include}>")?;
         } else {
-            writeln!(writer, "#include \"{}\"", self.include.to_str().unwrap())?;
+            writeln!(writer, "#include \"{include}\"")?;
         }
         Ok(())
     }
 }
 
-
 impl CppParam {
     fn params_as_args(params: &[CppParam]) -> String {
         params
@@ -287,16 +480,16 @@ impl CppParam {
             })
             .join(", ")
     }
-    fn params_as_args_no_default(params: &[CppParam]) -> String {
+    pub fn params_as_args_no_default(params: &[CppParam]) -> String {
         params
             .iter()
             .map(|p| format!("{}{} {}", p.ty, p.modifiers, p.name))
             .join(", ")
     }
-    fn params_names(params: &[CppParam]) -> String {
+    pub fn params_names(params: &[CppParam]) -> String {
         params.iter().map(|p| &p.name).join(", ")
     }
-    fn params_types(params: &[CppParam]) -> String {
+    pub fn params_types(params: &[CppParam]) -> String {
         params.iter().map(|p| &p.ty).join(", ")
     }
 
@@ -316,26 +509,40 @@ impl Writable for CppField {
             self.name, self.ty, self.offset
         )?;
 
-        let cpp_name = if self.literal_value.is_some() {
-            format!("_{}", &self.name)
-        } else {
-            self.name.to_string()
-        };
+        let cpp_name = &self.storage_name;
 
         match self.use_wrapper {
             // no wrapper
             false => writeln!(
                 writer,
-                "{}{} {} = {}",
-                if self.instance { "" } else { "inline static " },
+                "{}{} {} = {};",
+                match (self.instance, self.readonly) {
+                    (true, _) => "",
+                    (false, true) => "static constexpr ",
+                    (false, false) => "inline static ",
+                },
                 self.ty,
-                self.name,
+                self.cpp_name,
                 self.literal_value.as_ref().unwrap_or(&"{}".to_string())
             )?,
             // wrapper
             true => {
                 if let Some(literal) = &self.literal_value {
-                    writeln!(writer, "constexpr {} {} = {literal};", self.ty, self.name)?;
+                    writeln!(
+                        writer,
+                        "constexpr {} {} = {literal};",
+                        self.ty, self.cpp_name
+                    )?;
+                }
+                if self.instance && self.lookup_offset {
+                    let accessor = writer
+                        .profile
+                        .instance_field_lookup(&self.ty, cpp_name, &self.name);
+                    writer.write_code(&accessor)?;
+                    return Ok(());
+                }
+                if writer.profile == RuntimeProfile::Il2cppApi {
+                    return self.write_il2cpp_api_accessor(writer, cpp_name);
                 }
                 if self.instance {
                     writeln!(
@@ -356,6 +563,34 @@ impl Writable for CppField {
         Ok(())
     }
 }
+
+impl CppField {
+    fn write_il2cpp_api_accessor(
+        &self,
+        writer: &mut super::writer::CppWriter,
+        cpp_name: &str,
+    ) -> color_eyre::Result<()> {
+        if self.instance {
+            writeln!(
+                writer,
+                "{}& {cpp_name}() {{ return *reinterpret_cast<{}*>(reinterpret_cast<uint8_t*>(this) + 0x{:x}); }}",
+                self.ty, self.ty, self.offset
+            )?;
+        } else {
+            writer.write_code(&format!(
+                "static {} {cpp_name}() {{
+{} ___internal_value{{}};
+::il2cpp_field_static_get_value(::il2cpp_class_get_field_from_name({}(), \"{}\"), &___internal_value);
+return ___internal_value;
+}}",
+                self.ty, self.ty, self.classof_call, self.name
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Writable for CppMethodDecl {
     // declaration
     fn write(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
@@ -391,11 +626,8 @@ impl Writable for CppMethodDecl {
 impl Writable for CppMethodImpl {
     // declaration
     fn write(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
+        self.holder_template.write(writer)?;
         self.template.write(writer)?;
-        
-        if !self.instance {
-            write!(writer, "static ")?;
-        }
 
         // Start
         writeln!(
@@ -406,6 +638,8 @@ impl Writable for CppMethodImpl {
             self.cpp_method_name,
             CppParam::params_as_args_no_default(&self.parameters)
         )?;
+        writer.indent();
+        self.template.write_constraints(writer)?;
 
         //   static auto ___internal__logger = ::Logger::get().WithContext("::Org::BouncyCastle::Crypto::Parameters::DHPrivateKeyParameters::Equals");
         //   auto* ___internal__method = THROW_UNLESS((::il2cpp_utils::FindMethod(this, "Equals", std::vector<Il2CppClass*>{}, ::std::vector<const Il2CppType*>{::il2cpp_utils::ExtractType(obj)})));
@@ -414,27 +648,82 @@ impl Writable for CppMethodImpl {
         // Body
 
         let complete_type_name = format!("{}::{}", self.holder_cpp_namespaze, self.holder_cpp_name);
-        let params_format = CppParam::params_types(&self.parameters);
+        match (writer.profile, self.vtable_slot) {
+            (RuntimeProfile::BsHook, Some(slot)) => {
+                writeln!(writer, "auto* ___internal_method = THROW_UNLESS(::il2cpp_utils::ResolveVtableSlot((*reinterpret_cast<Il2CppObject**>(this))->klass, {}(), {slot}));",
+                    writer.profile.classof_call(&complete_type_name))?;
 
-        writeln!(writer, "static auto ___internal_method = ::il2cpp_utils::il2cpp_type_check::MetadataGetter<static_cast<{} ({complete_type_name}::*)({params_format})>(&{complete_type_name}::{})>::methodInfo();",
-            self.return_type,
-            self.cpp_method_name)?;
+                write!(
+                    writer,
+                    "return ::il2cpp_utils::RunMethodRethrow<{}, false>(this, ___internal_method",
+                    self.return_type
+                )?;
 
-        write!(
-            writer,
-            "return ::il2cpp_utils::RunMethodRethrow<{}, false>(this, ___internal__method,",
-            self.return_type
-        )?;
+                let param_names = CppParam::params_names(&self.parameters);
 
-        let param_names = CppParam::params_names(&self.parameters);
+                if !param_names.is_empty() {
+                    write!(writer, ", {}", param_names)?;
+                }
 
-        if !param_names.is_empty() {
-            write!(writer, ", {}", param_names)?;
-        }
+                writeln!(writer, ");")?;
+            }
+            (RuntimeProfile::BsHook, None) => {
+                let params_format = CppParam::params_types(&self.parameters);
+                let member_pointer = match self.instance {
+                    true => format!("{complete_type_name}::*"),
+                    false => "*".to_string(),
+                };
+                // Generic methods use the specialization of their instantiation
+                let method_name = match self.template.names.is_empty() {
+                    true => self.cpp_method_name.clone(),
+                    false => format!(
+                        "template {}{}",
+                        self.cpp_method_name,
+                        self.template.as_args()
+                    ),
+                };
+
+                writeln!(writer, "static auto ___internal_method = ::il2cpp_utils::il2cpp_type_check::MetadataGetter<static_cast<{} ({member_pointer})({params_format})>(&{complete_type_name}::{method_name})>::methodInfo();",
+                    self.return_type)?;
+
+                write!(
+                    writer,
+                    "return ::il2cpp_utils::RunMethodRethrow<{}, false>({}, ___internal_method",
+                    self.return_type,
+                    match self.instance {
+                        true => "this",
+                        false => "static_cast<Il2CppObject*>(nullptr)",
+                    }
+                )?;
+
+                let param_names = CppParam::params_names(&self.parameters);
+
+                if !param_names.is_empty() {
+                    write!(writer, ", {}", param_names)?;
+                }
 
-        writeln!(writer, ");")?;
+                writeln!(writer, ");")?;
+            }
+            (RuntimeProfile::Il2cppApi, _) => {
+                let invoke = il2cpp_api_invoke(
+                    &writer.profile.classof_call(&complete_type_name),
+                    self.method_token,
+                    match self.instance {
+                        true => "this",
+                        false => "nullptr",
+                    },
+                    &self.parameters,
+                    self.vtable_slot.is_some(),
+                );
+                writer.write_code(&invoke)?;
+                if let Some(ret) = il2cpp_api_return(&self.return_type, self.return_kind) {
+                    writer.write_code(&ret)?;
+                }
+            }
+        }
 
         // End
+        writer.dedent();
         writeln!(writer, "}}")?;
         Ok(())
     }
@@ -446,12 +735,21 @@ impl Writable for CppConstructorDecl {
         writeln!(writer, "// Ctor Parameters {:?}", self.parameters)?;
 
         self.template.write(writer)?;
-        writeln!(
-            writer,
-            "{}({});",
-            self.ty,
-            CppParam::params_as_args(&self.parameters)
-        )?;
+        match writer.profile {
+            RuntimeProfile::BsHook => writeln!(
+                writer,
+                "{}({});",
+                self.ty,
+                CppParam::params_as_args(&self.parameters)
+            )?,
+            // Objects are made by il2cpp, so there are no C++ constructors
+            RuntimeProfile::Il2cppApi => writeln!(
+                writer,
+                "static {}* New_ctor({});",
+                self.ty,
+                CppParam::params_as_args(&self.parameters)
+            )?,
+        }
         Ok(())
     }
 }
@@ -463,11 +761,15 @@ impl Writable for CppConstructorImpl {
         // Constructor
         self.template.write(writer)?;
 
+        if !self.is_constexpr && writer.profile == RuntimeProfile::Il2cppApi {
+            return self.write_il2cpp_api_factory(writer);
+        }
+
         if self.is_constexpr {
             // TODO:
             write!(
                 writer,
-                "inline {}({})",
+                "constexpr {}({})",
                 self.holder_cpp_ty_name,
                 CppParam::params_as_args(&self.parameters)
             )?;
@@ -508,6 +810,43 @@ impl Writable for CppConstructorImpl {
     }
 }
 
+impl CppConstructorImpl {
+    fn write_il2cpp_api_factory(
+        &self,
+        writer: &mut super::writer::CppWriter,
+    ) -> color_eyre::Result<()> {
+        writeln!(
+            writer,
+            "{}* {}::New_ctor({}) {{",
+            self.holder_cpp_ty_name,
+            self.holder_cpp_ty_name,
+            CppParam::params_as_args_no_default(&self.parameters)
+        )?;
+        writer.indent();
+        writeln!(
+            writer,
+            "auto* ___internal_object = ::il2cpp_object_new({}());",
+            self.classof_call
+        )?;
+        writer.write_code(&il2cpp_api_invoke(
+            &self.classof_call,
+            self.method_token,
+            "___internal_object",
+            &self.parameters,
+            false,
+        ))?;
+        writeln!(
+            writer,
+            "return reinterpret_cast<{}*>(___internal_object);",
+            self.holder_cpp_ty_name
+        )?;
+        writer.dedent();
+        writeln!(writer, "}}")?;
+
+        Ok(())
+    }
+}
+
 impl Writable for CppProperty {
     fn write(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
         writeln!(
@@ -525,6 +864,11 @@ impl Writable for CppProperty {
             return Ok(());
         }
 
+        // The get_ and set_ methods are the accessors
+        if writer.profile == RuntimeProfile::Il2cppApi {
+            return Ok(());
+        }
+
         if self.instance {
             writeln!(
                 writer,
@@ -533,7 +877,7 @@ impl Writable for CppProperty {
                 self.name,
                 self.getter.is_some(),
                 self.setter.is_some(),
-                self.name
+                self.cpp_name
             )?;
         } else {
             writeln!(
@@ -544,10 +888,108 @@ impl Writable for CppProperty {
                 self.getter.is_some(),
                 self.setter.is_some(),
                 self.classof_call,
-                self.name
+                self.cpp_name
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Writable for CppEvent {
+    fn write(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
+        writeln!(
+            writer,
+            "// Event: name: {}, Type Name: {}, add {} remove {} raise {}",
+            self.name,
+            self.ty,
+            self.add.is_some(),
+            self.remove.is_some(),
+            self.raise.is_some()
+        )?;
+
+        let prefix = match self.instance {
+            true => "",
+            false => "static ",
+        };
+
+        if let Some(add) = &self.add {
+            writeln!(
+                writer,
+                "{prefix}void {}_add({} handler) {{ {add}(handler); }}",
+                self.cpp_name, self.ty
+            )?;
+        }
+        if let Some(remove) = &self.remove {
+            writeln!(
+                writer,
+                "{prefix}void {}_remove({} handler) {{ {remove}(handler); }}",
+                self.cpp_name, self.ty
+            )?;
+        }
+        // raise takes whatever the event was declared with
+        if let Some(raise) = &self.raise {
+            writeln!(
+                writer,
+                "template<typename... TArgs>\n{prefix}void {}_raise(TArgs&&... args) {{ {raise}(::std::forward<TArgs>(args)...); }}",
+                self.cpp_name
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Writable for CppNestedUnion {
+    fn write(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
+        writeln!(
+            writer,
+            "// Overlapping fields at offset 0x{:x}",
+            self.offset
+        )?;
+        writeln!(writer, "uint8_t {}[0x{:x}];", self.storage_name, self.size)?;
+
+        for field in &self.fields {
+            writeln!(
+                writer,
+                "// Field: name: {}, Type Name: {}, Offset: 0x{:x}",
+                field.name, field.ty, field.offset
+            )?;
+            let start = field.offset - self.offset;
+            writeln!(
+                writer,
+                "{0}& {1}() noexcept {{ return *reinterpret_cast<{0}*>(this->{2} + 0x{start:x}); }}",
+                field.ty, field.cpp_name, self.storage_name
+            )?;
+            writeln!(
+                writer,
+                "{0} const& {1}() const noexcept {{ return *reinterpret_cast<{0} const*>(this->{2} + 0x{start:x}); }}",
+                field.ty, field.cpp_name, self.storage_name
             )?;
         }
+        Ok(())
+    }
+}
+
+impl Writable for CppDelegateHelpers {
+    fn write(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
+        writeln!(writer, "// Delegate")?;
+        for helper in writer.profile.delegate_helpers(
+            &self.cpp_name,
+            &self.return_type,
+            &self.parameters,
+            &self.invoke_name,
+            &self.new_name,
+        ) {
+            writer.write_code(&helper)?;
+        }
+        Ok(())
+    }
+}
 
+impl Writable for CppPadding {
+    fn write(&self, writer: &mut super::writer::CppWriter) -> color_eyre::Result<()> {
+        writeln!(writer, "uint8_t {}[0x{:x}];", self.cpp_name, self.size)?;
         Ok(())
     }
 }
@@ -559,42 +1001,185 @@ impl Writable for CppMethodSizeStruct {
             "//  Writing Method size for method: {}.{}",
             self.complete_type_name, self.cpp_method_name
         )?;
-        let params_format = CppParam::params_types(&self.params);
-
-        let method_info_rhs = 
+        // il2cpp-api looks up methods by token instead
+        if writer.profile == RuntimeProfile::Il2cppApi {
+            writeln!(
+                writer,
+                "// Addr 0x{:x} Size 0x{:x}",
+                self.method_data.addrs, self.method_data.estimated_size
+            )?;
+            return Ok(());
+        }
 
-        if let Some(slot) = self.slot && !self.is_final {
-            format!("THROW_UNLESS(::il2cpp_utils::ResolveVtableSlot((*reinterpret_cast<Il2CppObject**>(this))->klass, {}(), {slot}))", 
-              self.interface_clazz_of
-            )
-        } else {
-            format!("THROW_UNLESS(::il2cpp_utils::FindMethod(this, \"{}\", std::vector<Il2CppClass*>{{}}, ::std::vector<const Il2CppType*>{{{params_format}}}))", 
-                self.cpp_method_name
-            )
+        let params_format = CppParam::params_types(&self.params);
+        // methodInfo() is static and names the declared method, overrides are resolved when called
+        let classof_call = writer.profile.classof_call(&self.complete_type_name);
+
+        let method_info_rhs = format!("THROW_UNLESS(::il2cpp_utils::FindMethod({classof_call}(), \"{}\", std::vector<Il2CppClass*>{{{}}}, ::std::vector<const Il2CppType*>{{{}}}))", 
+            self.cs_method_name,
+            self.generic_args
+                .iter()
+                .map(|a| format!("{}()", writer.profile.classof_call(a)))
+                .join(", "),
+            self.params
+                .iter()
+                .map(|p| format!("::il2cpp_utils::ExtractIndependentType<{}>()", p.ty))
+                .join(", ")
+        );
+
+        // Instantiations name the template they come from
+        let method_name = match self.generic_args.is_empty() {
+            true => self.cpp_method_name.clone(),
+            false => format!(
+                "template {}<{}>",
+                self.cpp_method_name,
+                self.generic_args.join(",")
+            ),
         };
 
+        let member_pointer = match self.instance {
+            true => format!("{}::*", self.complete_type_name),
+            false => "*".to_string(),
+        };
 
-        writeln!(
-            writer,
+        writer.write_code(&format!(
             "template<>
-struct ::il2cpp_utils::il2cpp_type_check::MetadataGetter<static_cast<{} ({}::*)({params_format})>(&{}::{})> {{
-  constexpr static const usize size() {{
-    return 0x{:x};
-  }}
-  constexpr static const usize addrs() {{
-    return 0x{:x};
-  }}
-
-  inline static const ::MethodInfo* methodInfo() {{
-    return {method_info_rhs};
-  }}
+struct ::il2cpp_utils::il2cpp_type_check::MetadataGetter<static_cast<{} ({member_pointer})({params_format})>(&{}::{method_name})> {{
+constexpr static const usize size() {{
+return 0x{:x};
+}}
+constexpr static const usize addrs() {{
+return 0x{:x};
+}}
+
+inline static const ::MethodInfo* methodInfo() {{
+return {method_info_rhs};
+}}
 }};",
             self.ret_ty,
             self.complete_type_name,
-            self.complete_type_name,
-            self.cpp_method_name,
             self.method_data.estimated_size,
             self.method_data.addrs
+        ))?;
+        Ok(())
+    }
+}
+
+impl Writable for CppClassLookup {
+    fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        let Some(lookup) =
+            writer
+                .profile
+                .class_lookup(&self.image, &self.namespace, &self.names, self.generic)
+        else {
+            return Ok(());
+        };
+
+        writeln!(writer, "// Class lookup")?;
+        writer.write_code(&lookup)?;
+        Ok(())
+    }
+}
+
+impl CppClassInstantiation {
+    fn write_decl(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        if let Some(decl) = writer
+            .profile
+            .class_instantiation_decl(&self.complete_type_name)
+        {
+            writer.write_code(&decl)?;
+        }
+        Ok(())
+    }
+
+    fn write_impl(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        if let Some(lookup) = writer.profile.class_instantiation(
+            &self.complete_type_name,
+            &self.lookup.image,
+            &self.lookup.namespace,
+            &self.lookup.names,
+            &self.argument_classes,
+        ) {
+            writer.write_code(&lookup)?;
+        }
+        Ok(())
+    }
+}
+
+impl Writable for CppInterfaceWrapper {
+    fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        let Some((_, ctor)) = writer.profile.interface_wrapper(&self.cpp_name) else {
+            return Ok(());
+        };
+
+        writeln!(writer, "// Interface wrapper")?;
+        writer.write_code(&ctor)?;
+        Ok(())
+    }
+}
+
+impl Writable for CppInterfaceConversions {
+    fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        writeln!(writer, "// Implemented interfaces")?;
+        for conversion in writer.profile.interface_conversions(
+            &self.interfaces,
+            &self.as_name,
+            self.boxed_classof.as_deref(),
+        ) {
+            writer.write_code(&conversion)?;
+        }
+        Ok(())
+    }
+}
+
+impl CppEnumConstant {
+    fn write_decl(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        writeln!(writer, "// Enum value: {}", self.name)?;
+        writeln!(writer, "static const {} {};", self.enum_ty, self.cpp_name)?;
+        Ok(())
+    }
+
+    fn write_impl(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        self.holder_template.write(writer)?;
+        writeln!(
+            writer,
+            "constexpr {} {}::{}{{{}}};",
+            self.enum_ty, self.enum_ty, self.cpp_name, self.value
+        )?;
+        Ok(())
+    }
+}
+
+impl Writable for CppEnumOperators {
+    fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()> {
+        let name = &self.cpp_name;
+        let value = &self.value_name;
+
+        // Explicit, otherwise comparing against a constant is ambiguous
+        writeln!(
+            writer,
+            "explicit constexpr operator {}() const {{ return {value}; }}",
+            self.backing_ty
+        )?;
+        for op in ["==", "!="] {
+            writeln!(writer, "friend constexpr bool operator{op}({name} a, {name} b) {{ return a.{value} {op} b.{value}; }}")?;
+        }
+
+        if !self.flags {
+            return Ok(());
+        }
+        for op in ["|", "&", "^"] {
+            writeln!(writer, "friend constexpr {name} operator{op}({name} a, {name} b) {{ return {name}(a.{value} {op} b.{value}); }}")?;
+            writeln!(writer, "friend constexpr {name}& operator{op}=({name}& a, {name} b) {{ a.{value} = a.{value} {op} b.{value}; return a; }}")?;
+        }
+        writeln!(
+            writer,
+            "friend constexpr {name} operator~({name} a) {{ return {name}(~a.{value}); }}"
+        )?;
+        writeln!(
+            writer,
+            "constexpr bool {}({name} flag) const {{ return ({value} & flag.{value}) == flag.{value}; }}",
+            self.has_flag_name
         )?;
         Ok(())
     }
@@ -606,10 +1191,22 @@ impl Writable for CppMember {
             CppMember::Field(f) => f.write(writer),
             CppMember::MethodDecl(m) => m.write(writer),
             CppMember::Property(p) => p.write(writer),
+            CppMember::Event(e) => e.write(writer),
+            CppMember::NestedUnion(u) => u.write(writer),
             CppMember::Comment(c) => c.write(writer),
             CppMember::MethodImpl(i) => i.write(writer),
             CppMember::ConstructorDecl(c) => c.write(writer),
             CppMember::ConstructorImpl(ci) => ci.write(writer),
+            CppMember::EnumConstantDecl(c) => c.write_decl(writer),
+            CppMember::EnumConstantImpl(c) => c.write_impl(writer),
+            CppMember::EnumOperators(o) => o.write(writer),
+            CppMember::ClassLookup(l) => l.write(writer),
+            CppMember::ClassInstantiationDecl(i) => i.write_decl(writer),
+            CppMember::ClassInstantiationImpl(i) => i.write_impl(writer),
+            CppMember::InterfaceWrapper(w) => w.write(writer),
+            CppMember::InterfaceConversions(c) => c.write(writer),
+            CppMember::Padding(p) => p.write(writer),
+            CppMember::DelegateHelpers(d) => d.write(writer),
         }
     }
 }
@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Cursor};
 
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 use il2cpp_binary::{CodeRegistration, MetadataRegistration, Type, TypeData};
-use il2cpp_metadata_raw::{Il2CppTypeDefinition, MethodIndex, TypeDefinitionIndex};
+use il2cpp_metadata_raw::{
+    Il2CppImageDefinition, Il2CppTypeDefinition, MethodIndex, TypeDefinitionIndex,
+};
 use itertools::Itertools;
 
 use super::{
@@ -10,6 +13,28 @@ use super::{
     cs_type::CSType,
 };
 
+/// Size of a pointer on the target, from the ELF class of libil2cpp.so
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerSize {
+    Bytes4 = 4,
+    Bytes8 = 8,
+}
+
+impl PointerSize {
+    pub fn from_elf(elf_data: &[u8]) -> color_eyre::Result<PointerSize> {
+        // e_ident[EI_CLASS]
+        match elf_data.get(4) {
+            Some(1) => Ok(PointerSize::Bytes4),
+            Some(2) => Ok(PointerSize::Bytes8),
+            class => Err(color_eyre::eyre::eyre!("Unknown ELF class {class:?}")),
+        }
+    }
+
+    pub fn bytes(&self) -> u32 {
+        *self as u32
+    }
+}
+
 pub struct MethodCalculations {
     pub estimated_size: usize,
     pub addrs: u64,
@@ -31,9 +56,16 @@ pub struct Metadata<'a> {
     pub metadata: &'a il2cpp_metadata_raw::Metadata<'a>,
     pub metadata_registration: &'a MetadataRegistration,
     pub code_registration: &'a CodeRegistration<'a>,
+    pub pointer_size: PointerSize,
 
     // Method index in metadata
     pub method_calculations: HashMap<MethodIndex, MethodCalculations>,
+    // (method index, class inst, method inst) of a method spec, -1 when not instantiated
+    pub generic_method_calculations: HashMap<(MethodIndex, i32, i32), MethodCalculations>,
+    // (class inst, method inst) of each instantiated generic method, sorted
+    pub generic_method_instantiations: HashMap<MethodIndex, Vec<(i32, i32)>>,
+    // Generic class indices of each generic type definition
+    pub generic_class_instantiations: HashMap<TypeDefinitionIndex, Vec<usize>>,
     pub parent_to_child_map: HashMap<TypeDefinitionIndex, Vec<TypeDefinitionPair<'a>>>,
     pub child_to_parent_map: HashMap<TypeDefinitionIndex, TypeDefinitionPair<'a>>,
 }
@@ -109,6 +141,7 @@ impl<'a> Metadata<'a> {
             .code_gen_modules
             .iter()
             .flat_map(|m| &m.method_pointers)
+            .chain(&self.code_registration.generic_method_pointers)
             .copied()
             .collect();
         method_addresses_sorted.sort();
@@ -167,5 +200,200 @@ impl<'a> Metadata<'a> {
                 method_calculations
             })
             .collect();
+
+        let estimate = |method_pointer: u64| {
+            let sorted_address_index = *method_addresses_sorted_map.get(&method_pointer).unwrap();
+            let next_method_pointer = method_addresses_sorted
+                .get(sorted_address_index + 1)
+                .cloned()
+                .unwrap_or(0);
+
+            MethodCalculations {
+                estimated_size: method_pointer.abs_diff(next_method_pointer) as usize,
+                addrs: method_pointer,
+            }
+        };
+
+        // method spec -> address of the instantiated method
+        self.generic_method_calculations = self
+            .metadata_registration
+            .generic_method_table
+            .iter()
+            .filter_map(|entry| {
+                let spec = self
+                    .metadata_registration
+                    .method_specs
+                    .get(entry.generic_method_index as usize)?;
+                let method_pointer = *self
+                    .code_registration
+                    .generic_method_pointers
+                    .get(entry.indices.method_index as usize)?;
+
+                Some((
+                    (
+                        spec.method_definition_index,
+                        spec.class_inst_index,
+                        spec.method_inst_index,
+                    ),
+                    estimate(method_pointer),
+                ))
+            })
+            .collect();
+
+        self.generic_method_instantiations = self
+            .generic_method_calculations
+            .keys()
+            .filter(|(_, _, method_inst)| *method_inst != -1)
+            .map(|(method, class_inst, method_inst)| (*method, (*class_inst, *method_inst)))
+            .into_group_map();
+        self.generic_method_instantiations
+            .values_mut()
+            .for_each(|insts| insts.sort());
+
+        self.generic_class_instantiations = self
+            .metadata_registration
+            .generic_classes
+            .iter()
+            .enumerate()
+            .map(|(index, gc)| (gc.type_definition_index, index))
+            .into_group_map();
+    }
+
+    /// The C# name of a type including its namespace
+    /// Nested types are joined to their declaring type with `/`
+    pub fn type_full_name(&self, tdi: TypeDefinitionIndex) -> String {
+        let ty = &self.metadata.type_definitions[tdi as usize];
+        let name = self.metadata.get_str(ty.name_index).unwrap();
+
+        if let Some(parent) = self.child_to_parent_map.get(&tdi) {
+            return format!("{}/{name}", self.type_full_name(parent.tdi));
+        }
+
+        let ns = self.metadata.get_str(ty.namespace_index).unwrap();
+        if ns.is_empty() {
+            name.to_string()
+        } else {
+            format!("{ns}.{name}")
+        }
+    }
+
+    /// The name of the image a type is defined in, e.g. Assembly-CSharp.dll
+    pub fn type_image_name(&self, tdi: TypeDefinitionIndex) -> &str {
+        self.type_image(tdi)
+            .map(|i| self.metadata.get_str(i.name_index).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn type_image(&self, tdi: TypeDefinitionIndex) -> Option<&Il2CppImageDefinition> {
+        self.metadata
+            .images
+            .iter()
+            .find(|i| (i.type_start..i.type_start + i.type_count).contains(&tdi))
+    }
+
+    /// The types of the custom attributes applied to a type, e.g. System.FlagsAttribute
+    pub fn type_custom_attributes(&self, tdi: TypeDefinitionIndex) -> Vec<TypeDefinitionIndex> {
+        let ty = &self.metadata.type_definitions[tdi as usize];
+        let Some(image) = self.type_image(tdi) else {
+            return vec![];
+        };
+
+        // Each image has its own ranges, sorted by token
+        let Some(range) = self
+            .metadata
+            .attribute_data_range
+            .get(
+                image.custom_attribute_start as usize
+                    ..(image.custom_attribute_start + image.custom_attribute_count) as usize,
+            )
+            .and_then(|ranges| ranges.iter().find(|r| r.token == ty.token))
+        else {
+            return vec![];
+        };
+
+        // The data starts with the attribute count, then the constructor of each attribute
+        let Some(data) = self
+            .metadata
+            .attribute_data
+            .get(range.start_offset as usize..)
+        else {
+            return vec![];
+        };
+        let mut cursor = Cursor::new(data);
+        let Ok(count) = read_compressed_u32(&mut cursor) else {
+            return vec![];
+        };
+
+        (0..count)
+            .map_while(|_| cursor.read_u32::<LittleEndian>().ok())
+            .filter_map(|ctor| self.metadata.methods.get(ctor as usize))
+            .map(|ctor| ctor.declaring_type)
+            .collect()
+    }
+}
+
+/// Reads an unsigned integer in il2cpp's compressed encoding, taking 1 to 5 bytes
+fn read_compressed_u32(cursor: &mut Cursor<&[u8]>) -> std::io::Result<u32> {
+    let first = cursor.read_u8()? as u32;
+    match first {
+        _ if first & 0x80 == 0 => Ok(first),
+        _ if first & 0xC0 == 0x80 => Ok(((first & !0x80) << 8) | cursor.read_u8()? as u32),
+        _ if first & 0xE0 == 0xC0 => Ok(((first & !0xC0) << 24) | cursor.read_u24::<BigEndian>()?),
+        0xF0 => cursor.read_u32::<LittleEndian>(),
+        0xFE => Ok(u32::MAX - 1),
+        0xFF => Ok(u32::MAX),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid compressed integer prefix {first:#x}"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8]) -> std::io::Result<(u32, u64)> {
+        let mut cursor = Cursor::new(bytes);
+        let value = read_compressed_u32(&mut cursor)?;
+        Ok((value, cursor.position()))
+    }
+
+    #[test]
+    fn reads_compressed_u32_at_each_width() {
+        // Value, encoding
+        let table: &[(u32, &[u8])] = &[
+            (0, &[0x00]),
+            (0x7F, &[0x7F]),
+            (0x80, &[0x80, 0x80]),
+            (0x3FFF, &[0xBF, 0xFF]),
+            (0x4000, &[0xC0, 0x00, 0x40, 0x00]),
+            (0x1FFFFFFF, &[0xDF, 0xFF, 0xFF, 0xFF]),
+            (0x20000000, &[0xF0, 0x00, 0x00, 0x00, 0x20]),
+            (0x12345678, &[0xF0, 0x78, 0x56, 0x34, 0x12]),
+            (u32::MAX - 1, &[0xFE]),
+            (u32::MAX, &[0xFF]),
+        ];
+
+        for (value, bytes) in table {
+            // Trailing bytes belong to whatever comes next
+            let with_trailing = [*bytes, &[0xAA]].concat();
+            assert_eq!(
+                read(&with_trailing).unwrap(),
+                (*value, bytes.len() as u64),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_compressed_u32() {
+        assert!(read(&[0xE0]).is_err());
+        assert!(read(&[0xF1, 0, 0, 0, 0]).is_err());
+        // Cut short
+        assert!(read(&[]).is_err());
+        assert!(read(&[0x80]).is_err());
+        assert!(read(&[0xC0, 0x00]).is_err());
+        assert!(read(&[0xF0, 0x00, 0x00, 0x00]).is_err());
     }
 }
@@ -5,4 +5,5 @@ pub mod cpp_type;
 pub mod cs_type;
 pub mod members;
 pub mod metadata;
+pub mod profile;
 pub mod writer;
@@ -0,0 +1,414 @@
+use clap::ValueEnum;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use super::{config::WrapperHeaders, members::CppParam};
+
+/// The runtime support library generated code is written against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeProfile {
+    /// beatsaber-hook wrapper types and il2cpp_utils
+    #[default]
+    BsHook,
+    /// Raw il2cpp object pointers and the il2cpp-api functions
+    Il2cppApi,
+}
+
+impl RuntimeProfile {
+    pub fn default_headers(&self) -> WrapperHeaders {
+        match self {
+            RuntimeProfile::BsHook => WrapperHeaders::default(),
+            RuntimeProfile::Il2cppApi => WrapperHeaders {
+                wrapper_type: "il2cpp-object-internals.h".into(),
+                string: "il2cpp-object-internals.h".into(),
+                array: "il2cpp-object-internals.h".into(),
+                runtime: "il2cpp-api.h".into(),
+            },
+        }
+    }
+
+    pub fn object_type(&self) -> String {
+        match self {
+            RuntimeProfile::BsHook => "::bs_hook::Il2CppWrapperType".to_string(),
+            RuntimeProfile::Il2cppApi => "::Il2CppObject*".to_string(),
+        }
+    }
+
+    pub fn string_type(&self) -> String {
+        match self {
+            RuntimeProfile::BsHook => "::StringW".to_string(),
+            RuntimeProfile::Il2cppApi => "::Il2CppString*".to_string(),
+        }
+    }
+
+    pub fn array_type(&self, element: &str) -> String {
+        match self {
+            RuntimeProfile::BsHook => format!("::ArrayW<{element}>"),
+            RuntimeProfile::Il2cppApi => "::Il2CppArray*".to_string(),
+        }
+    }
+
+    /// How a reference type is passed around
+    /// bs-hook types are wrappers, il2cpp-api types are the object itself
+    pub fn reference_type(&self, cpp_name: &str) -> String {
+        match self {
+            RuntimeProfile::BsHook => cpp_name.to_string(),
+            RuntimeProfile::Il2cppApi => format!("{cpp_name}*"),
+        }
+    }
+
+    /// The base and constructor of an interface wrapper
+    /// il2cpp-api interfaces are only ever pointed to, so they need neither
+    pub fn interface_wrapper(&self, name: &str) -> Option<(String, String)> {
+        match self {
+            RuntimeProfile::BsHook => Some((
+                self.object_type(),
+                format!(
+                    "explicit {name}(void* ptr) : {}(ptr) {{}}",
+                    self.object_type()
+                ),
+            )),
+            RuntimeProfile::Il2cppApi => None,
+        }
+    }
+
+    /// Converting to an implemented interface, checked against `interfaces`
+    /// Value types are boxed first through `boxed_classof`, as an interface is always an object
+    pub fn interface_conversions(
+        &self,
+        interfaces: &[String],
+        as_name: &str,
+        boxed_classof: Option<&str>,
+    ) -> Vec<String> {
+        let implemented = interfaces
+            .iter()
+            .map(|i| format!("::std::is_same_v<T, {i}>"))
+            .join(" || ");
+
+        match self {
+            RuntimeProfile::BsHook => {
+                let instance = match boxed_classof {
+                    Some(classof) => format!(
+                        "::il2cpp_functions::value_box({classof}(), const_cast<void*>(static_cast<const void*>(this)))"
+                    ),
+                    None => "this->convert()".to_string(),
+                };
+                interfaces
+                    .iter()
+                    .map(|i| format!("operator {i}() const noexcept {{ return {i}({instance}); }}"))
+                    .chain([format!(
+                        "template<typename T> T {as_name}() const noexcept {{
+static_assert({implemented}, \"Not an implemented interface\");
+return static_cast<T>(*this);
+}}"
+                    )])
+                    .collect()
+            }
+            RuntimeProfile::Il2cppApi => {
+                let instance = match boxed_classof {
+                    Some(classof) => format!("::il2cpp_value_box({classof}(), this)"),
+                    None => "this".to_string(),
+                };
+                vec![format!(
+                    "template<typename T> T* {as_name}() noexcept {{
+static_assert({implemented}, \"Not an implemented interface\");
+return reinterpret_cast<T*>({instance});
+}}"
+                )]
+            }
+        }
+    }
+
+    /// Calling a delegate like a function through `invoke_name`, and making one from C++
+    pub fn delegate_helpers(
+        &self,
+        name: &str,
+        return_type: &str,
+        params: &[CppParam],
+        invoke_name: &str,
+        new_name: &str,
+    ) -> Vec<String> {
+        let call = format!(
+            "{return_type} operator()({}) {{ return this->{invoke_name}({}); }}",
+            CppParam::params_as_args_no_default(params),
+            CppParam::params_names(params)
+        );
+        let signature = format!("{return_type}({})", CppParam::params_types(params));
+
+        match self {
+            RuntimeProfile::BsHook => vec![
+                call,
+                format!(
+                    "static {name} {new_name}(::std::function<{signature}> f) {{ return ::il2cpp_utils::MakeDelegate<{name}>(::std::move(f)); }}"
+                ),
+                format!(
+                    "static {name} {new_name}({return_type} (*f)({})) {{ return ::il2cpp_utils::MakeDelegate<{name}>(f); }}",
+                    CppParam::params_types(params)
+                ),
+            ],
+            // Making delegates needs a native trampoline, which il2cpp-api doesn't have
+            RuntimeProfile::Il2cppApi => vec![
+                call,
+                "// Constructing delegates from C++ is not supported with il2cpp-api".to_string(),
+            ],
+        }
+    }
+
+    /// Whether the generic argument `t` is a value type
+    /// il2cpp-api passes every reference type as a pointer
+    pub fn value_type_check(&self, t: &str) -> String {
+        match self {
+            RuntimeProfile::BsHook => {
+                format!("::il2cpp_utils::il2cpp_type_check::need_box<{t}>::value")
+            }
+            RuntimeProfile::Il2cppApi => format!("!::std::is_pointer_v<{t}>"),
+        }
+    }
+
+    /// Whether the generic argument `t` can be made without arguments
+    /// Value types always can, il2cpp-api reference types need a parameterless `New_ctor`
+    pub fn default_constructible_check(&self, t: &str) -> String {
+        match self {
+            RuntimeProfile::BsHook => format!("::std::is_default_constructible_v<{t}>"),
+            RuntimeProfile::Il2cppApi => format!(
+                "!::std::is_pointer_v<{t}> || requires {{ ::std::remove_pointer_t<{t}>::New_ctor(); }}"
+            ),
+        }
+    }
+
+    /// Whether the generic argument `t` derives from or implements `base`
+    /// il2cpp-api types only convert to their interfaces through `as`, so those can't be checked
+    pub fn base_type_check(&self, t: &str, base: &str, is_interface: bool) -> Option<String> {
+        match (self, is_interface) {
+            (RuntimeProfile::BsHook, true) => Some(format!(
+                "{} || ::std::is_convertible_v<{t}, {base}>",
+                self.value_type_check(t)
+            )),
+            (RuntimeProfile::Il2cppApi, true) => None,
+            (_, false) => Some(format!("::std::is_convertible_v<{t}, {base}>")),
+        }
+    }
+
+    /// The function returning the `Il2CppClass*` of a type
+    pub fn classof_call(&self, complete_cpp_name: &str) -> String {
+        match self {
+            RuntimeProfile::BsHook => format!(
+                "::il2cpp_utils::il2cpp_type_check::il2cpp_no_arg_class<{complete_cpp_name}>::get"
+            ),
+            RuntimeProfile::Il2cppApi => format!("{complete_cpp_name}::___il2cpp_class"),
+        }
+    }
+
+    /// An accessor for an instance field of an open generic, which is laid out per instantiation
+    /// The offset is looked up by name in the instance's class, once for each instantiation
+    pub fn instance_field_lookup(&self, ty: &str, cpp_name: &str, name: &str) -> String {
+        let (instance, offset) = match self {
+            RuntimeProfile::BsHook => (
+                "*reinterpret_cast<::Il2CppObject**>(this)",
+                format!(
+                    "THROW_UNLESS(::il2cpp_utils::FindField(___internal_instance->klass, \"{name}\"))->offset"
+                ),
+            ),
+            RuntimeProfile::Il2cppApi => (
+                "reinterpret_cast<::Il2CppObject*>(this)",
+                format!(
+                    "::il2cpp_field_get_offset(::il2cpp_class_get_field_from_name(::il2cpp_object_get_class(___internal_instance), \"{name}\"))"
+                ),
+            ),
+        };
+
+        format!(
+            "{ty}& {cpp_name}() {{
+auto* ___internal_instance = {instance};
+static auto ___internal_offset = {offset};
+return *reinterpret_cast<{ty}*>(reinterpret_cast<uint8_t*>(___internal_instance) + ___internal_offset);
+}}"
+        )
+    }
+
+    /// il2cpp-api has no classof, so each type looks up its own class
+    /// A generic definition's class is not any instantiation's, so those each define their own
+    pub fn class_lookup(
+        &self,
+        image: &str,
+        namespace: &str,
+        names: &[String],
+        generic: bool,
+    ) -> Option<String> {
+        match (self, generic) {
+            (RuntimeProfile::BsHook, _) => None,
+            (RuntimeProfile::Il2cppApi, true) => {
+                Some("static ::Il2CppClass* ___il2cpp_class();".to_string())
+            }
+            (RuntimeProfile::Il2cppApi, false) => Some(format!(
+                "static ::Il2CppClass* ___il2cpp_class() {{
+static auto* ___internal_class = {};
+return ___internal_class;
+}}",
+                il2cpp_api_definition_lookup(image, namespace, names)
+            )),
+        }
+    }
+
+    /// Declares the class lookup of a generic instantiation, see `class_instantiation`
+    pub fn class_instantiation_decl(&self, complete_cpp_name: &str) -> Option<String> {
+        match self {
+            RuntimeProfile::BsHook => None,
+            RuntimeProfile::Il2cppApi => Some(format!(
+                "template<>\n::Il2CppClass* {complete_cpp_name}::___il2cpp_class();"
+            )),
+        }
+    }
+
+    /// The class lookup of a generic instantiation, the definition made generic through reflection
+    /// il2cpp-api can't make a generic instance type itself
+    pub fn class_instantiation(
+        &self,
+        complete_cpp_name: &str,
+        image: &str,
+        namespace: &str,
+        names: &[String],
+        argument_classes: &[String],
+    ) -> Option<String> {
+        if *self == RuntimeProfile::BsHook {
+            return None;
+        }
+
+        Some(format!(
+            "template<>
+inline ::Il2CppClass* {complete_cpp_name}::___il2cpp_class() {{
+static auto* ___internal_class = [] {{
+auto* ___internal_definition = ::il2cpp_type_get_object(::il2cpp_class_get_type({}));
+::Il2CppClass* ___internal_arguments[] = {{ {} }};
+auto* ___internal_types = ::il2cpp_array_new(::il2cpp_class_from_name(::il2cpp_get_corlib(), \"System\", \"Type\"), {});
+for (int i = 0; i < {}; i++) {{
+reinterpret_cast<::Il2CppArraySize*>(___internal_types)->vector[i] = ::il2cpp_type_get_object(::il2cpp_class_get_type(___internal_arguments[i]));
+}}
+auto* ___internal_method = ::il2cpp_class_get_method_from_name(::il2cpp_object_get_class(___internal_definition), \"MakeGenericType\", 1);
+void* ___internal_args[] = {{ ___internal_types }};
+::Il2CppException* ___internal_exception = nullptr;
+auto* ___internal_type = ::il2cpp_runtime_invoke(___internal_method, ___internal_definition, ___internal_args, &___internal_exception);
+if (___internal_exception) throw ::Il2CppExceptionWrapper(___internal_exception);
+return ::il2cpp_class_from_system_type(reinterpret_cast<::Il2CppReflectionType*>(___internal_type));
+}}();
+return ___internal_class;
+}}",
+            il2cpp_api_definition_lookup(image, namespace, names),
+            argument_classes.join(", "),
+            argument_classes.len(),
+            argument_classes.len()
+        ))
+    }
+}
+
+/// Finds the class of a type definition by name, an open generic's is its definition
+/// Nested types are not found by name, so they are searched for in their declaring class
+fn il2cpp_api_definition_lookup(image: &str, namespace: &str, names: &[String]) -> String {
+    let (root, nested) = names.split_first().unwrap();
+    let root_lookup = format!(
+        "::il2cpp_class_from_name(::il2cpp_assembly_get_image(::il2cpp_domain_assembly_open(::il2cpp_domain_get(), \"{}\")), \"{namespace}\", \"{root}\")",
+        image.trim_end_matches(".dll")
+    );
+    if nested.is_empty() {
+        return root_lookup;
+    }
+
+    format!(
+        "[] {{
+auto* ___internal_class = {root_lookup};
+for (::std::string_view ___internal_name : {{ {} }}) {{
+void* ___internal_iter = nullptr;
+auto* ___internal_declaring = ___internal_class;
+___internal_class = nullptr;
+while (auto* k = ___internal_declaring ? ::il2cpp_class_get_nested_types(___internal_declaring, &___internal_iter) : nullptr) {{
+if (___internal_name == ::il2cpp_class_get_name(k)) {{
+___internal_class = k;
+break;
+}}
+}}
+}}
+return ___internal_class;
+}}()",
+        nested.iter().map(|n| format!("\"{n}\"")).join(", ")
+    )
+}
+
+/// Body lines for calling a method through `il2cpp_runtime_invoke`
+/// The method is found by its metadata token, so overloads resolve exactly
+/// Interface methods are resolved again against the instance's class
+pub fn il2cpp_api_invoke(
+    classof_call: &str,
+    token: u32,
+    instance: &str,
+    params: &[CppParam],
+    virtual_dispatch: bool,
+) -> String {
+    // Zero sized arrays are not valid C++
+    let args = match params.is_empty() {
+        true => "void** ___internal_args = nullptr;".to_string(),
+        false => format!(
+            "void* ___internal_args[] = {{ {} }};",
+            params
+                .iter()
+                .map(|p| format!("___internal_arg({})", p.name))
+                .join(", ")
+        ),
+    };
+
+    let method = match virtual_dispatch {
+        true => format!(
+            "::il2cpp_object_get_virtual_method(reinterpret_cast<::Il2CppObject*>({instance}), ___internal_method)"
+        ),
+        false => "___internal_method".to_string(),
+    };
+
+    format!(
+        "static const ::MethodInfo* ___internal_method = [] {{
+void* ___internal_iter = nullptr;
+while (auto* m = ::il2cpp_class_get_methods({classof_call}(), &___internal_iter)) {{
+if (::il2cpp_method_get_token(m) == 0x{token:x}) return m;
+}}
+return static_cast<const ::MethodInfo*>(nullptr);
+}}();
+auto ___internal_arg = [](auto& v) -> void* {{
+if constexpr (::std::is_pointer_v<::std::remove_reference_t<decltype(v)>>) return v;
+else return &v;
+}};
+{args}
+::Il2CppException* ___internal_exception = nullptr;
+auto* ___internal_result = ::il2cpp_runtime_invoke({method}, {instance}, ___internal_args, &___internal_exception);
+if (___internal_exception) throw ::Il2CppExceptionWrapper(___internal_exception);"
+    )
+}
+
+/// How `il2cpp_runtime_invoke` gives back a method's result
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReturnKind {
+    /// The object itself
+    Reference,
+    /// A boxed copy
+    Value,
+    /// Either, depending on what a generic parameter is instantiated with
+    GenericParameter,
+}
+
+/// Converts the result of `il2cpp_api_invoke` to the return type
+pub fn il2cpp_api_return(return_type: &str, kind: ReturnKind) -> Option<String> {
+    if return_type == "void" {
+        return None;
+    }
+
+    let reference = format!("return reinterpret_cast<{return_type}>(___internal_result);");
+    let value = format!(
+        "return *reinterpret_cast<{return_type}*>(::il2cpp_object_unbox(___internal_result));"
+    );
+    Some(match kind {
+        ReturnKind::Reference => reference,
+        ReturnKind::Value => value,
+        // Only in templates, where the branch not taken is never instantiated
+        ReturnKind::GenericParameter => {
+            format!("if constexpr (::std::is_pointer_v<{return_type}>) {reference}\nelse {value}")
+        }
+    })
+}
@@ -1,36 +1,78 @@
 use std::{fs::File, io::Write};
 
+use super::{config::IndentStyle, profile::RuntimeProfile};
+
 pub struct CppWriter {
     pub stream: File,
     pub indent: u16,
     pub newline: bool,
+    pub profile: RuntimeProfile,
+    pub indent_style: IndentStyle,
+    // Whether the last line written was empty, so blank lines don't pile up
+    blank: bool,
 }
 
 impl CppWriter {
+    pub fn new(stream: File, profile: RuntimeProfile, indent_style: IndentStyle) -> Self {
+        Self {
+            stream,
+            indent: 0,
+            newline: true,
+            profile,
+            indent_style,
+            // Nothing to separate from at the start of a file
+            blank: true,
+        }
+    }
+
     pub fn indent(&mut self) {
         self.indent += 1;
     }
     pub fn dedent(&mut self) {
-        if self.indent == 0 {
-            panic!("Indent should not be dedented when already less than 4!")
+        // An unbalanced dedent only makes the output look off, it is not worth failing over
+        self.indent = self.indent.saturating_sub(1);
+    }
+
+    /// Writes generated code line by line, indenting what is between `{` and `}`
+    /// so multi-line code follows the indent style like everything else
+    pub fn write_code(&mut self, code: &str) -> std::io::Result<()> {
+        for line in code.lines().map(str::trim) {
+            if line.starts_with('}') {
+                self.dedent();
+            }
+            writeln!(self, "{line}")?;
+            if line.ends_with('{') {
+                self.indent();
+            }
+        }
+        Ok(())
+    }
+
+    /// Separates sections with a single empty line, however many are asked for
+    pub fn blank_line(&mut self) -> std::io::Result<()> {
+        if !self.blank {
+            writeln!(self)?;
         }
-        self.indent -= 1;
+        Ok(())
     }
 }
 
 impl Write for CppWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        // TODO: One day we will write indented
-        // if self.indent > 0 && self.newline {
-        //     self.stream.write_all("\t".repeat(self.indent.into()).as_bytes())?;
-        // }
-        self.newline = buf.ends_with(b"\n");
-        self.stream.write(buf)
-
-        // let buffer = str::repeat(" ", self.indent.into());
-        // self.stream.write_all(buffer.as_bytes())?;
-        // self.stream.write_all(buf)?;
-        // return Ok(buf.len());
+        // Prefix each line, leaving empty lines empty
+        for line in buf.split_inclusive(|b| *b == b'\n') {
+            if self.newline && line != b"\n" {
+                self.stream
+                    .write_all(self.indent_style.prefix(self.indent).as_bytes())?;
+            }
+            if self.newline {
+                self.blank = line == b"\n";
+            }
+            self.stream.write_all(line)?;
+            self.newline = line.ends_with(b"\n");
+        }
+
+        Ok(buf.len())
     }
     fn flush(&mut self) -> std::io::Result<()> {
         self.stream.flush()
@@ -40,3 +82,95 @@ impl Write for CppWriter {
 pub trait Writable: std::fmt::Debug {
     fn write(&self, writer: &mut CppWriter) -> color_eyre::Result<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// What `write` writes, with `indent_style`
+    fn written(
+        indent_style: IndentStyle,
+        write: impl FnOnce(&mut CppWriter) -> std::io::Result<()>,
+    ) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "cordl-{}-writer-{indent_style:?}.hpp",
+            std::process::id()
+        ));
+        let mut writer = CppWriter::new(
+            File::create(&path).unwrap(),
+            RuntimeProfile::BsHook,
+            indent_style,
+        );
+        write(&mut writer).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        content
+    }
+
+    fn write_nested(writer: &mut CppWriter) -> std::io::Result<()> {
+        writer.blank_line()?;
+        writeln!(writer, "struct A {{")?;
+        writer.indent();
+        // Written indented or not, it is indented the same
+        writer.write_code(
+            "void f() {
+                if (x) {
+                    return;
+                } else {
+                    g();
+                }
+            }",
+        )?;
+        writer.blank_line()?;
+        writer.blank_line()?;
+        writeln!(writer, "int x;")?;
+        writer.dedent();
+        writeln!(writer, "}};")?;
+        writer.dedent();
+        writer.blank_line()
+    }
+
+    #[test]
+    fn indents_nested_blocks_with_spaces() {
+        assert_eq!(
+            written(IndentStyle::Spaces(2), write_nested),
+            "struct A {
+  void f() {
+    if (x) {
+      return;
+    } else {
+      g();
+    }
+  }
+
+  int x;
+};
+
+"
+        );
+    }
+
+    #[test]
+    fn indents_nested_blocks_with_tabs() {
+        assert_eq!(
+            written(IndentStyle::Tabs, write_nested),
+            "struct A {\n\tvoid f() {\n\t\tif (x) {\n\t\t\treturn;\n\t\t} else {\n\t\t\tg();\n\t\t}\n\t}\n\n\tint x;\n};\n\n"
+        );
+    }
+
+    #[test]
+    fn leaves_empty_lines_unindented() {
+        assert_eq!(
+            written(IndentStyle::Spaces(4), |writer| {
+                writer.indent();
+                writeln!(writer, "a;\n\nb;")
+            }),
+            "    a;\n\n    b;\n"
+        );
+    }
+}
@@ -3,17 +3,22 @@
 #![feature(core_intrinsics)]
 #![feature(slice_as_chunks)]
 
-use generate::config::GenerationConfig;
-use generate::context::{CppContextCollection, TypeTag};
-use generate::metadata::Metadata;
+use color_eyre::eyre::{eyre, WrapErr};
+use generate::config::{ConfigFile, GenerationConfig, TypeFilter};
+use generate::constants::TypeDefinitionExtensions;
+use generate::context::CppContextCollection;
+use generate::metadata::{Metadata, PointerSize};
+use generate::profile::RuntimeProfile;
 
 use std::path::PathBuf;
 use std::{fs, time};
 
-use clap::{Parser, Subcommand};
-use il2cpp_binary::{Elf, TypeData};
+use clap::{Args, Parser, Subcommand};
+use glob::Pattern;
+use il2cpp_binary::{Elf, Type, TypeData};
+use il2cpp_metadata_raw::TypeDefinitionIndex;
+use itertools::Itertools;
 
-use crate::generate::members::CppMember;
 mod generate;
 
 #[derive(Parser)]
@@ -28,40 +33,117 @@ struct Cli {
     libil2cpp: PathBuf,
 
     #[clap(subcommand)]
-    command: Option<Commands>,
+    command: Commands,
 }
 
 #[derive(Subcommand)]
-enum Commands {}
+enum Commands {
+    /// Generate C++ headers for the il2cpp types
+    Generate {
+        /// The directory to write the generated code to [default: ./codegen]
+        #[clap(short, long, value_parser, value_name = "DIR")]
+        output: Option<PathBuf>,
+
+        /// A TOML or JSON config file for the generation
+        #[clap(short, long, value_parser, value_name = "FILE")]
+        config: Option<PathBuf>,
+
+        /// The runtime support library to generate code for [default: bs-hook]
+        #[clap(short, long, value_enum)]
+        profile: Option<RuntimeProfile>,
+
+        #[clap(flatten)]
+        filter: FilterArgs,
+    },
+    /// Print the metadata of a type
+    Dump {
+        /// The full name of the type, e.g. UnityEngine.Color or Parent/Nested
+        #[clap(value_parser)]
+        name: String,
+    },
+    /// Search types and methods by name
+    Query {
+        /// The (case insensitive) text to search for
+        #[clap(value_parser)]
+        query: String,
+
+        /// Also search method names
+        #[clap(long)]
+        methods: bool,
+    },
+}
+
+/// Filters for the root types to generate, types they depend on are always generated
+#[derive(Args)]
+struct FilterArgs {
+    /// Only generate types from this image, e.g. Assembly-CSharp.dll
+    #[clap(long, value_parser, value_name = "IMAGE")]
+    include_image: Vec<String>,
+
+    /// Do not generate types from this image
+    #[clap(long, value_parser, value_name = "IMAGE")]
+    exclude_image: Vec<String>,
+
+    /// Only generate types in this namespace or its children
+    #[clap(long, value_parser, value_name = "NAMESPACE")]
+    include_namespace: Vec<String>,
+
+    /// Do not generate types in this namespace or its children
+    #[clap(long, value_parser, value_name = "NAMESPACE")]
+    exclude_namespace: Vec<String>,
+
+    /// Only generate types whose full name matches this glob, e.g. UnityEngine.*
+    #[clap(long, value_parser, value_name = "GLOB")]
+    include_name: Vec<String>,
+
+    /// Do not generate types whose full name matches this glob
+    #[clap(long, value_parser, value_name = "GLOB")]
+    exclude_name: Vec<String>,
+}
+
+impl FilterArgs {
+    fn into_filter(self) -> color_eyre::Result<TypeFilter> {
+        let patterns = |globs: Vec<String>| -> color_eyre::Result<Vec<Pattern>> {
+            globs
+                .iter()
+                .map(|g| Pattern::new(g).wrap_err_with(|| format!("Invalid glob {g}")))
+                .collect()
+        };
+
+        Ok(TypeFilter {
+            include_images: self.include_image,
+            exclude_images: self.exclude_image,
+            include_namespaces: self.include_namespace,
+            exclude_namespaces: self.exclude_namespace,
+            include_names: patterns(self.include_name)?,
+            exclude_names: patterns(self.exclude_name)?,
+        })
+    }
+}
 
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
-    // let cli = Cli::parse();
-    let cli = Cli {
-        metadata: PathBuf::from("global-metadata.dat"),
-        libil2cpp: PathBuf::from("libil2cpp.so"),
-        command: None,
-    };
+    let cli = Cli::parse();
 
     let metadata_data = fs::read(cli.metadata)?;
     let il2cpp_metadata = il2cpp_metadata_raw::deserialize(&metadata_data)?;
 
     let elf_data = fs::read(cli.libil2cpp)?;
     let elf = Elf::parse(&elf_data)?;
+    let pointer_size = PointerSize::from_elf(&elf_data)?;
 
     let (code_registration, metadata_registration) =
         il2cpp_binary::registrations(&elf, &il2cpp_metadata)?;
 
-    let config = GenerationConfig {
-        header_path: PathBuf::from("./codegen/include"),
-        source_path: PathBuf::from("./codegen/src"),
-    };
-
     let mut metadata = Metadata {
         metadata: &il2cpp_metadata,
         code_registration: &code_registration,
         metadata_registration: &metadata_registration,
+        pointer_size,
         method_calculations: Default::default(),
+        generic_method_calculations: Default::default(),
+        generic_method_instantiations: Default::default(),
+        generic_class_instantiations: Default::default(),
         parent_to_child_map: Default::default(),
         child_to_parent_map: Default::default(),
     };
@@ -69,140 +151,231 @@ fn main() -> color_eyre::Result<()> {
     println!("Parsing metadata methods");
     metadata.parse();
     println!("Finished in {}ms", t.elapsed().as_millis());
+
+    match cli.command {
+        Commands::Generate {
+            output,
+            config,
+            profile,
+            filter,
+        } => {
+            let config_file = match config {
+                Some(path) => ConfigFile::load(&path)?,
+                None => ConfigFile::default(),
+            };
+            let config = config_file.into_config(output, profile, filter.into_filter()?);
+            generate(&metadata, &config)
+        }
+        Commands::Dump { name } => dump(&metadata, &name),
+        Commands::Query { query, methods } => {
+            query_types(&metadata, &query, methods);
+            Ok(())
+        }
+    }
+}
+
+fn generate(metadata: &Metadata, config: &GenerationConfig) -> color_eyre::Result<()> {
     let mut cpp_context_collection = CppContextCollection::new();
 
     // First, make all the contexts
     println!("Filling root types");
     for tdi in 0..metadata.metadata.type_definitions.len() {
-        if metadata.child_to_parent_map.contains_key(&tdi.try_into()?) {
+        let tdi: TypeDefinitionIndex = tdi.try_into()?;
+        if metadata.child_to_parent_map.contains_key(&tdi) {
             continue;
         }
-        cpp_context_collection.fill(
-            &metadata,
-            &config,
-            TypeData::TypeDefinitionIndex(tdi.try_into()?),
-        );
+        if !config.filter.matches(metadata, tdi) {
+            continue;
+        }
+        cpp_context_collection.fill(metadata, config, TypeData::TypeDefinitionIndex(tdi));
     }
-    // Fill children
-    println!("Nested types pass");
-    for (parent, _children) in &metadata.parent_to_child_map {
-        let owner = cpp_context_collection
-            .get_cpp_type(&metadata, &config, TypeData::TypeDefinitionIndex(*parent))
-            .unwrap();
-
-        // **Ignore this, we no longer recurse:**
-        // skip children of children
-        // only fill first grade children of types
-        // if owner.nested {
-        //     continue;
-        // }
-
-        let owner_ty = owner.self_tag;
-
-        cpp_context_collection.fill_nested_types(&metadata, &config, owner_ty);
+    // Then everything the filled types depend on
+    println!("Filling dependencies");
+    cpp_context_collection.fill_dependencies(metadata, config);
+    println!("Finding include cycles");
+    let cyclic = cpp_context_collection.find_include_cycles(metadata);
+
+    // Counted in types, a context writes its type and the nested types in it
+    println!("Writing all types");
+    let mut written = 0;
+    let mut skipped = 0;
+    let mut failed = 0;
+    for (tag, context) in cpp_context_collection.get() {
+        // A type that could not be made leaves its context empty
+        if context.get_types().is_empty() {
+            skipped += 1;
+            continue;
+        }
+        let types = context.get_types_flattened().count();
+        // Its headers could never compile
+        if cyclic.contains(tag) {
+            failed += types;
+            continue;
+        }
+
+        match context.write(config) {
+            Ok(()) => written += types,
+            Err(e) => {
+                failed += types;
+                println!("Failed to write {:?}: {e:?}", context.typedef_path);
+            }
+        }
     }
+    println!("Finished writing types: {written} written, {skipped} skipped, {failed} failed");
 
-    // for t in &metadata.type_definitions {
-    //     // Handle the generation for a single type
-    //     let dest = open_writer(&metadata, &config, &t);
-    //     write_type(&metadata, &config, &t, &dest);
-    // }
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(123)].write()?;
-    // cpp_context_collection.get()[&TypeTag::TypeDefinition(342)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(512)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(1024)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(600)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(1000)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(420)].write()?;
-    // cpp_context_collection.get()[&TypeTag::TypeDefinition(69)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(531)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(532)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(533)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(534)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(535)].write()?;
-    cpp_context_collection.get()[&TypeTag::TypeDefinition(1455)].write()?;
-    println!("Generic type");
-    cpp_context_collection
-        .get()
-        .iter()
-        .find(|(_, c)| {
-            c.get_types()
-                .iter()
-                .any(|(_, t)| !t.generic_args.names.is_empty())
-        })
-        .unwrap()
-        .1
-        .write()?;
-    println!("Value type");
-    cpp_context_collection
-        .get()
-        .iter()
-        .find(|(_, c)| {
-            c.get_types()
-                .iter()
-                .any(|(_, t)| t.is_value_type && t.name == "Color" && t.namespace == "UnityEngine")
-        })
-        .unwrap()
-        .1
-        .write()?;
-    println!("Nested type");
-    cpp_context_collection
-        .get()
-        .iter()
-        .find(|(_, c)| {
-            c.get_types()
-                .iter()
-                .any(|(_, t)| t.nested_types.iter().any(|n| !n.declarations.is_empty()))
-        })
-        .unwrap()
-        .1
-        .write()?;
-    println!("AlignmentUnion type");
-    cpp_context_collection
-        .get()
-        .iter()
-        .find(|(_, c)| {
-            c.get_types()
-                .iter()
-                .any(|(_, t)| t.is_value_type && t.name == "AlignmentUnion")
-        })
-        .unwrap()
-        .1
-        .write()?;
-    println!("Array type");
-    cpp_context_collection
-        .get()
-        .iter()
-        .find(|(_, c)| {
-            c.get_types()
-                .iter()
-                .any(|(_, t)| t.name == "Array" && t.namespace == "System")
-        })
-        .unwrap()
-        .1
-        .write()?;
-    println!("Default param");
-    cpp_context_collection
-        .get()
-        .iter()
-        .filter(|(_, c)| {
-            c.get_types().iter().any(|(_, t)| {
-                t.declarations.iter().any(|d| {
-                    if let CppMember::MethodDecl(m) = d {
-                        m.parameters.iter().any(|p| p.def_value.is_some())
-                    } else {
-                        false
-                    }
+    Ok(())
+}
+
+fn describe_type(metadata: &Metadata, ty: &Type) -> String {
+    match ty.data {
+        TypeData::TypeDefinitionIndex(tdi) => metadata.type_full_name(tdi),
+        TypeData::GenericClassIndex(gci) => {
+            let generic_class = &metadata.metadata_registration.generic_classes[gci];
+            let args = generic_class
+                .context
+                .class_inst_idx
+                .and_then(|idx| metadata.metadata_registration.generic_insts.get(idx))
+                .map(|inst| {
+                    inst.types
+                        .iter()
+                        .map(|t| describe_type(metadata, &metadata.metadata_registration.types[*t]))
+                        .join(", ")
                 })
+                .unwrap_or_default();
+
+            format!(
+                "{}<{args}>",
+                metadata.type_full_name(generic_class.type_definition_index)
+            )
+        }
+        TypeData::TypeIndex(ti) => {
+            format!(
+                "{}[]",
+                describe_type(metadata, &metadata.metadata_registration.types[ti])
+            )
+        }
+        TypeData::GenericParameterIndex(gpi) => {
+            let generic_param = &metadata.metadata.generic_parameters[gpi as usize];
+            metadata
+                .metadata
+                .get_str(generic_param.name_index)
+                .unwrap()
+                .to_string()
+        }
+        TypeData::ArrayType => format!("{:?}", ty.ty),
+    }
+}
+
+fn dump(metadata: &Metadata, name: &str) -> color_eyre::Result<()> {
+    let tdi = (0..metadata.metadata.type_definitions.len() as TypeDefinitionIndex)
+        .find(|tdi| metadata.type_full_name(*tdi) == name)
+        .ok_or_else(|| eyre!("No type found with name {name}"))?;
+    let t = &metadata.metadata.type_definitions[tdi as usize];
+
+    println!("Type: {name} (TypeDefinition {tdi})");
+    println!(
+        "Flags: 0x{:x} value type: {} enum: {} interface: {}",
+        t.flags,
+        t.is_value_type(),
+        t.is_enum_type(),
+        t.is_interface()
+    );
+    if let Some(parent) = metadata
+        .metadata_registration
+        .types
+        .get(t.parent_index as usize)
+    {
+        println!("Parent: {}", describe_type(metadata, parent));
+    }
+    for i in 0..t.interfaces_count as u32 {
+        let interface_index = metadata.metadata.interfaces[(t.interfaces_start + i) as usize];
+        let int_ty = &metadata.metadata_registration.types[interface_index as usize];
+        println!("Interface: {}", describe_type(metadata, int_ty));
+    }
+
+    println!("Fields:");
+    for i in 0..t.field_count as u32 {
+        let field = &metadata.metadata.fields[(t.field_start + i) as usize];
+        let f_type = &metadata.metadata_registration.types[field.type_index as usize];
+        let offset = metadata
+            .metadata_registration
+            .field_offsets
+            .get(tdi as usize)
+            .and_then(|offsets| offsets.get(i as usize))
+            .copied()
+            .unwrap_or_default();
+
+        println!(
+            "  0x{offset:x} {} {}",
+            describe_type(metadata, f_type),
+            metadata.metadata.get_str(field.name_index).unwrap()
+        );
+    }
+
+    println!("Properties:");
+    for i in 0..t.property_count as u32 {
+        let prop = &metadata.metadata.properties[(t.property_start + i) as usize];
+        println!(
+            "  {} get: {} set: {}",
+            metadata.metadata.get_str(prop.name_index).unwrap(),
+            prop.get != u32::MAX,
+            prop.set != u32::MAX
+        );
+    }
+
+    println!("Methods:");
+    for i in 0..t.method_count as u32 {
+        let method_index = t.method_start + i;
+        let method = &metadata.metadata.methods[method_index as usize];
+        let ret_ty = &metadata.metadata_registration.types[method.return_type as usize];
+        let params = (0..method.parameter_count as u32)
+            .map(|p| {
+                let param = &metadata.metadata.parameters[(method.parameter_start + p) as usize];
+                let param_ty = &metadata.metadata_registration.types[param.type_index as usize];
+                format!(
+                    "{} {}",
+                    describe_type(metadata, param_ty),
+                    metadata.metadata.get_str(param.name_index).unwrap()
+                )
             })
-        })
-        .nth(2)
-        .unwrap()
-        .1
-        .write()?;
-    // for (_, context) in cpp_context_collection.get() {
-    //     context.write().unwrap();
-    // }
+            .join(", ");
+
+        print!(
+            "  {} {}({params})",
+            describe_type(metadata, ret_ty),
+            metadata.metadata.get_str(method.name_index).unwrap()
+        );
+        match metadata.method_calculations.get(&method_index) {
+            Some(calc) => println!(" addr 0x{:x} size 0x{:x}", calc.addrs, calc.estimated_size),
+            None => println!(),
+        }
+    }
 
     Ok(())
 }
+
+fn query_types(metadata: &Metadata, query: &str, methods: bool) {
+    let query = query.to_lowercase();
+
+    for tdi in 0..metadata.metadata.type_definitions.len() as TypeDefinitionIndex {
+        let t = &metadata.metadata.type_definitions[tdi as usize];
+        let full_name = metadata.type_full_name(tdi);
+
+        if full_name.to_lowercase().contains(&query) {
+            println!("{full_name} (TypeDefinition {tdi})");
+        }
+
+        if !methods {
+            continue;
+        }
+
+        for i in 0..t.method_count as u32 {
+            let method = &metadata.metadata.methods[(t.method_start + i) as usize];
+            let m_name = metadata.metadata.get_str(method.name_index).unwrap();
+
+            if m_name.to_lowercase().contains(&query) {
+                println!("{full_name}::{m_name}");
+            }
+        }
+    }
+}